@@ -0,0 +1,314 @@
+use crate::chords::Chord;
+
+/// Samples per second of rendered audio.
+pub const SAMPLE_RATE: u32 = 44_100;
+
+/// Named strum patterns accepted in place of a spelled-out one.
+pub const STRUM_NAMES: [&str; 5] = ["down", "up", "down-up", "island", "arpeggio"];
+
+/// Delay between neighbouring strings in a strum, in seconds.
+const STRUM_SPREAD: f32 = 0.015;
+/// How much energy a plucked string keeps each round trip; lower dies faster.
+const DECAY: f32 = 0.996;
+/// How long the last chord rings on after the final bar, in seconds.
+const TAIL: f32 = 1.5;
+
+/// One eighth note of a strum pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stroke {
+    /// All sounding strings, G to A
+    Down,
+    /// All sounding strings, A to G
+    Up,
+    /// One string
+    Pick(UkeString),
+    Rest,
+}
+
+/// One of the four strings, named as in standard tuning whatever the
+/// tuning is, so a picked string always exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UkeString {
+    G,
+    C,
+    E,
+    A,
+}
+
+impl UkeString {
+    /// In G C E A order.
+    pub const ALL: [UkeString; 4] = [UkeString::G, UkeString::C, UkeString::E, UkeString::A];
+
+    /// Index into a chord's frets or a tuning: 0 = G … 3 = A.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl Stroke {
+    /// Read a pattern, one eighth note per character: `D` and `U` strum
+    /// down and up, `1`-`4` pick one string (1 = G), `-` or a space rests.
+    /// Also accepts the names in `STRUM_NAMES`.
+    pub fn parse_pattern(text: &str) -> Result<Vec<Stroke>, String> {
+        let spelled = match text {
+            "down" => "D-D-D-D-",
+            "up" => "U-U-U-U-",
+            "down-up" => "DUDUDUDU",
+            "island" => "D-DU-UDU",
+            "arpeggio" => "12343212",
+            other => other,
+        };
+        let pattern = spelled
+            .chars()
+            .map(|c| match c.to_ascii_uppercase() {
+                'D' => Ok(Stroke::Down),
+                'U' => Ok(Stroke::Up),
+                '-' | ' ' => Ok(Stroke::Rest),
+                '1'..='4' => Ok(Stroke::Pick(UkeString::ALL[c as usize - '1' as usize])),
+                _ => Err(format!(
+                    "`{}` in strum pattern `{}` (use D, U, 1-4 and -, or one of {})",
+                    c,
+                    text,
+                    STRUM_NAMES.join(", ")
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if pattern.is_empty() {
+            return Err("empty strum pattern".into());
+        }
+        Ok(pattern)
+    }
+}
+
+/// How a progression is played.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strumming {
+    /// Quarter notes per minute
+    pub tempo: u32,
+    /// What each eighth note of a bar does; a bar is as long as its pattern
+    pub pattern: Vec<Stroke>,
+    /// Bars each chord lasts
+    pub bars: u32,
+}
+
+impl Default for Strumming {
+    fn default() -> Self {
+        Strumming {
+            tempo: 90,
+            pattern: Stroke::parse_pattern("island").expect("built-in pattern"),
+            bars: 1,
+        }
+    }
+}
+
+impl Strumming {
+    /// Length of an eighth note, in seconds.
+    pub fn eighth(&self) -> f32 {
+        30.0 / self.tempo.max(1) as f32
+    }
+
+    /// Every note played, in time order: when it starts (seconds), the
+    /// string (0 = G) and the MIDI note. Strums spread their strings
+    /// `spread` seconds apart.
+    pub fn notes<'a>(
+        &self,
+        chords: impl IntoIterator<Item = &'a Chord>,
+        tuning: [u8; 4],
+        spread: f32,
+    ) -> Vec<(f32, usize, u8)> {
+        let mut notes = Vec::new();
+        let mut slot = 0;
+        for chord in chords {
+            let sounding = |string: usize| chord.frets[string].map(|fret| tuning[string] + fret);
+            for _ in 0..self.bars {
+                for &stroke in &self.pattern {
+                    let start = slot as f32 * self.eighth();
+                    let strings: Vec<usize> = match stroke {
+                        Stroke::Down => (0..4).collect(),
+                        Stroke::Up => (0..4).rev().collect(),
+                        Stroke::Pick(string) => vec![string.index()],
+                        Stroke::Rest => Vec::new(),
+                    };
+                    let mut played = 0;
+                    for string in strings {
+                        if let Some(midi) = sounding(string) {
+                            notes.push((start + played as f32 * spread, string, midi));
+                            played += 1;
+                        }
+                    }
+                    slot += 1;
+                }
+            }
+        }
+        notes
+    }
+}
+
+/// Play `chords` one after another with plucked strings, each for
+/// `strumming.bars` bars of its pattern. Mono samples between -1 and 1 at
+/// `SAMPLE_RATE`.
+pub fn render<'a>(
+    chords: impl IntoIterator<Item = &'a Chord>,
+    tuning: [u8; 4],
+    strumming: &Strumming,
+) -> Vec<f32> {
+    let chords: Vec<&Chord> = chords.into_iter().collect();
+    let notes = strumming.notes(chords.iter().copied(), tuning, STRUM_SPREAD);
+    let bars = chords.len() as f32 * strumming.bars as f32;
+    let length = bars * strumming.pattern.len() as f32 * strumming.eighth() + TAIL;
+    let mut out = vec![0.0; (length * SAMPLE_RATE as f32) as usize];
+
+    // Each string rings until it is plucked again, like a real one
+    let mut plucks: [Vec<(usize, u8)>; 4] = Default::default();
+    for &(start, string, midi) in &notes {
+        plucks[string].push(((start * SAMPLE_RATE as f32) as usize, midi));
+    }
+    for (string, plucks) in plucks.iter().enumerate() {
+        for (n, &(start, midi)) in plucks.iter().enumerate() {
+            let end = plucks.get(n + 1).map_or(out.len(), |&(next, _)| next);
+            let seed = (string as u32 + 1) * 7919 + n as u32;
+            pluck(&mut out[start.min(end)..end], midi, seed);
+        }
+    }
+    out
+}
+
+/// Karplus–Strong: a delay line one period long, filled with noise and
+/// averaged as it recirculates, mixed into `out`.
+fn pluck(out: &mut [f32], midi: u8, seed: u32) {
+    let frequency = 440.0 * 2f32.powf((f32::from(midi) - 69.0) / 12.0);
+    let period = ((SAMPLE_RATE as f32 / frequency).round() as usize).max(2);
+    // A small xorshift generator keeps renders identical run to run
+    let mut state = seed.max(1);
+    let mut line: Vec<f32> = (0..period)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as f32 / u32::MAX as f32 * 2.0 - 1.0
+        })
+        .collect();
+    for (i, sample) in out.iter_mut().enumerate() {
+        let at = i % period;
+        let next = line[(at + 1) % period];
+        let current = line[at];
+        *sample += current * 0.25;
+        line[at] = DECAY * 0.5 * (current + next);
+    }
+}
+
+/// Samples as a 16-bit mono PCM WAV file.
+pub fn wav(samples: &[f32]) -> Vec<u8> {
+    let data = (samples.len() * 2) as u32;
+    let mut out = Vec::with_capacity(44 + data as usize);
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes()); // format chunk size
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&1u16.to_le_bytes()); // mono
+    out.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    out.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // bytes per second
+    out.extend_from_slice(&2u16.to_le_bytes()); // bytes per frame
+    out.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data.to_le_bytes());
+    for &sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theory::STANDARD_TUNING;
+
+    fn chord(name: &str, frets: &str) -> Chord {
+        Chord::from_string(name, frets).expect("valid chord")
+    }
+
+    #[test]
+    fn reads_patterns_and_names() {
+        use Stroke::*;
+        assert_eq!(
+            Stroke::parse_pattern("d-u 14"),
+            Ok(vec![
+                Down,
+                Rest,
+                Up,
+                Rest,
+                Pick(UkeString::G),
+                Pick(UkeString::A)
+            ])
+        );
+        assert_eq!(
+            Stroke::parse_pattern("island"),
+            Stroke::parse_pattern("D-DU-UDU")
+        );
+        for name in STRUM_NAMES {
+            assert!(Stroke::parse_pattern(name).is_ok(), "{}", name);
+        }
+        assert!(Stroke::parse_pattern("").is_err());
+        assert!(Stroke::parse_pattern("D5").is_err());
+        assert!(Stroke::parse_pattern("0").is_err());
+    }
+
+    #[test]
+    fn times_strokes_by_eighth_notes() {
+        let strumming = Strumming {
+            tempo: 120,
+            pattern: Stroke::parse_pattern("DU-4").expect("pattern"),
+            bars: 1,
+        };
+        assert_eq!(strumming.eighth(), 0.25);
+        let am = chord("Am", "2 0 0 X");
+        let notes = strumming.notes([&am], STANDARD_TUNING, 0.01);
+        // Down G C E, the muted A skipped; up E C G; a rest; the A picked
+        // plays nothing
+        assert_eq!(
+            notes,
+            vec![
+                (0.0, 0, 69),
+                (0.01, 1, 60),
+                (0.02, 2, 64),
+                (0.25, 2, 64),
+                (0.26, 1, 60),
+                (0.27, 0, 69),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_every_bar_and_the_tail() {
+        let c = chord("C", "0 0 0 3");
+        let strumming = Strumming {
+            tempo: 60,
+            pattern: Stroke::parse_pattern("D---").expect("pattern"),
+            bars: 2,
+        };
+        let samples = render([&c, &c], STANDARD_TUNING, &strumming);
+        // Four bars of four half-second eighths, then the tail
+        let seconds = 4.0 * 4.0 * 0.5 + TAIL;
+        assert_eq!(samples.len(), (seconds * SAMPLE_RATE as f32) as usize);
+        assert!(samples.iter().any(|&s| s != 0.0));
+    }
+
+    #[test]
+    fn writes_a_wav_header() {
+        let bytes = wav(&[0.0, 1.0, -1.0]);
+        assert_eq!(bytes.len(), 44 + 6);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(bytes[4..8], (36u32 + 6).to_le_bytes());
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        assert_eq!(bytes[16..20], 16u32.to_le_bytes());
+        assert_eq!(bytes[20..24], [1, 0, 1, 0]);
+        assert_eq!(bytes[24..28], SAMPLE_RATE.to_le_bytes());
+        assert_eq!(bytes[28..32], (SAMPLE_RATE * 2).to_le_bytes());
+        assert_eq!(bytes[32..36], [2, 0, 16, 0]);
+        assert_eq!(&bytes[36..40], b"data");
+        assert_eq!(bytes[40..44], 6u32.to_le_bytes());
+        assert_eq!(bytes[44..], [0, 0, 0xFF, 0x7F, 0x01, 0x80]);
+    }
+}
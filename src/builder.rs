@@ -0,0 +1,315 @@
+use uke_tui::chords::{self, Chord, NECK_FRETS};
+use uke_tui::theory::{self, Accidentals};
+use uke_tui::widgets::{Fretboard, Orientation, View};
+
+use crate::config::Config;
+use crate::editor::LineEditor;
+
+/// Most edits remembered for undo.
+const UNDO_LIMIT: usize = 100;
+
+type Frets = [Option<u8>; 4];
+
+/// Where a click on the fretboard landed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hit {
+    /// On the open/muted side of the nut, for a string index
+    Nut(usize),
+    /// A fret cell: string index and fret number
+    Fret(usize, u8),
+}
+
+/// Interactive chord builder: a blank fretboard that names whatever is fretted.
+pub struct Builder {
+    frets: Frets,
+    /// Name prompt while saving to the library
+    pub naming: Option<LineEditor>,
+    /// Library chord being edited, offered as the default name when saving
+    pub source: Option<String>,
+    /// Keyboard cursor: row on the fretboard (0 = top string) and fret (0 = nut)
+    cursor: (usize, u8),
+    undo: Vec<Frets>,
+    redo: Vec<Frets>,
+    tuning: [u8; 4],
+    accidentals: Accidentals,
+    /// Handedness and string order; the neck is always horizontal
+    view: View,
+}
+
+impl Builder {
+    /// Start from an existing shape, or all strings open.
+    pub fn new(frets: Option<Frets>, config: &Config) -> Self {
+        Self {
+            frets: frets.unwrap_or([Some(0); 4]),
+            naming: None,
+            source: None,
+            cursor: (0, 0),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            tuning: config.tuning,
+            accidentals: config.accidentals,
+            view: View {
+                orientation: Orientation::Horizontal,
+                ..config.view()
+            },
+        }
+    }
+
+    /// Edit a library chord, keeping its name for "save as".
+    pub fn editing(chord: &Chord, config: &Config) -> Self {
+        Self {
+            source: Some(chord.name.clone()),
+            ..Self::new(Some(chord.frets), config)
+        }
+    }
+
+    pub fn frets(&self) -> Frets {
+        self.frets
+    }
+
+    /// Every fretted note must lie on the neck.
+    pub fn validate(&self) -> Result<(), String> {
+        match chords::off_neck(&self.frets) {
+            Some(f) => Err(chords::past_neck(f)),
+            None => Ok(()),
+        }
+    }
+
+    /// Put a finger on `fret`, or lift it if it is already there.
+    pub fn toggle_fret(&mut self, string: usize, fret: u8) {
+        let fret = if self.frets[string] == Some(fret) {
+            0
+        } else {
+            fret
+        };
+        self.set(string, Some(fret));
+    }
+
+    /// Switch a string between open and muted.
+    pub fn toggle_nut(&mut self, string: usize) {
+        let open = self.frets[string] != Some(0);
+        self.set(string, open.then_some(0));
+    }
+
+    /// Open every string.
+    pub fn clear(&mut self) {
+        self.record([Some(0); 4]);
+    }
+
+    /// Move the keyboard cursor by whole strings and frets, stopping at the edges.
+    pub fn move_cursor(&mut self, rows: isize, frets: isize) {
+        let (row, fret) = self.cursor;
+        let row = row.saturating_add_signed(rows).min(self.frets.len() - 1);
+        let fret = (fret as isize + frets).clamp(0, NECK_FRETS as isize) as u8;
+        self.cursor = (row, fret);
+    }
+
+    /// Move the keyboard cursor by fret cells on screen: to the right is up
+    /// the neck unless the neck is mirrored for a left-handed player.
+    pub fn move_along(&mut self, cells: isize) {
+        let frets = if self.view.frets_reversed() {
+            -cells
+        } else {
+            cells
+        };
+        self.move_cursor(0, frets);
+    }
+
+    /// Index into `frets` of the string under the cursor.
+    pub fn cursor_string(&self) -> usize {
+        self.view.strings()[self.cursor.0]
+    }
+
+    /// Space: toggle whatever is under the cursor, as a click would.
+    pub fn toggle_at_cursor(&mut self) {
+        match self.cursor.1 {
+            0 => self.toggle_nut(self.cursor_string()),
+            fret => self.toggle_fret(self.cursor_string(), fret),
+        }
+    }
+
+    /// Mute the string under the cursor.
+    pub fn mute_at_cursor(&mut self) {
+        self.set(self.cursor_string(), None);
+    }
+
+    /// Slide the cursor string's finger up or down the neck, following it with
+    /// the cursor. Refuses to go past the last fret, and leaves a muted
+    /// string muted.
+    pub fn slide(&mut self, delta: i8) -> Result<(), String> {
+        let string = self.cursor_string();
+        let Some(from) = self.frets[string] else {
+            return Err("That string is muted; open it at the nut first".into());
+        };
+        let to = from as i16 + delta as i16;
+        if to > NECK_FRETS as i16 {
+            return Err(chords::past_neck(to as u8));
+        }
+        let to = to.max(0) as u8;
+        self.set(string, Some(to));
+        self.cursor.1 = to;
+        Ok(())
+    }
+
+    /// Move the cursor to a clicked target.
+    pub fn point_at(&mut self, hit: &Hit) {
+        let (string, fret) = match *hit {
+            Hit::Nut(string) => (string, 0),
+            Hit::Fret(string, fret) => (string, fret),
+        };
+        let row = self
+            .view
+            .strings()
+            .iter()
+            .position(|&s| s == string)
+            .unwrap_or(0);
+        self.cursor = (row, fret);
+    }
+
+    /// Step back one edit; false when there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(frets) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(self.frets);
+        self.frets = frets;
+        true
+    }
+
+    /// Reapply an undone edit; false when there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(frets) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(self.frets);
+        self.frets = frets;
+        true
+    }
+
+    /// Sounding MIDI notes.
+    pub fn pitches(&self) -> Vec<u8> {
+        self.frets
+            .iter()
+            .zip(self.tuning)
+            .filter_map(|(f, open)| f.map(|n| open + n))
+            .collect()
+    }
+
+    /// Names for the current shape: library entries with these exact frets,
+    /// then whatever the notes spell.
+    pub fn names(&self, library: &[Chord]) -> Vec<String> {
+        let mut names: Vec<String> = library
+            .iter()
+            .filter(|c| c.frets == self.frets)
+            .map(|c| c.name.clone())
+            .collect();
+        for name in theory::identify(&self.pitches(), self.accidentals) {
+            if !names.iter().any(|n| n == &name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Name offered when saving: the chord being edited, else the best match.
+    pub fn default_name(&self, library: &[Chord]) -> Option<String> {
+        self.source.clone().or_else(|| {
+            self.names(library)
+                .first()
+                .map(|n| n.trim_end_matches(" (no 5)").to_string())
+        })
+    }
+
+    /// The neck with this shape on it and the keyboard cursor bracketed.
+    pub fn fretboard(&self) -> Fretboard {
+        let (row, fret) = self.cursor;
+        Fretboard::new(self.frets)
+            .length(NECK_FRETS)
+            .tuning(self.tuning)
+            .accidentals(self.accidentals)
+            .view(self.view)
+            .cursor(Some((self.view.strings()[row], fret)))
+    }
+
+    /// Map a position relative to the top-left of `fretboard()` to a target.
+    pub fn hit(&self, x: u16, y: u16) -> Option<Hit> {
+        match self.fretboard().hit(x, y)? {
+            (string, 0) => Some(Hit::Nut(string)),
+            (string, fret) => Some(Hit::Fret(string, fret)),
+        }
+    }
+
+    fn set(&mut self, string: usize, fret: Option<u8>) {
+        let mut frets = self.frets;
+        frets[string] = fret;
+        self.record(frets);
+    }
+
+    /// Apply a new shape, remembering the old one for undo.
+    fn record(&mut self, frets: Frets) {
+        if frets == self.frets {
+            return;
+        }
+        self.undo.push(self.frets);
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.frets = frets;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slides_along_the_neck_but_not_off_it() {
+        let mut builder = Builder::new(
+            Some([Some(0), Some(0), Some(0), Some(11)]),
+            &Config::default(),
+        );
+        // The cursor starts on the top string: A unless G is put on top
+        let a = builder.cursor_string();
+        assert_eq!(a, 3);
+        assert!(builder.slide(1).is_ok());
+        assert_eq!(builder.frets()[a], Some(12));
+        assert!(builder.slide(1).is_err());
+        assert_eq!(builder.frets()[a], Some(12));
+        builder.slide(-12).unwrap();
+        builder.slide(-1).unwrap();
+        assert_eq!(builder.frets()[a], Some(0));
+        assert!(builder.validate().is_ok());
+    }
+
+    #[test]
+    fn leaves_muted_strings_muted() {
+        let mut builder = Builder::new(None, &Config::default());
+        builder.mute_at_cursor();
+        let string = builder.cursor_string();
+        assert!(builder.slide(-1).is_err());
+        assert!(builder.slide(1).is_err());
+        assert_eq!(builder.frets()[string], None);
+        // Undo still steps back over the mute alone
+        assert!(builder.undo());
+        assert_eq!(builder.frets()[string], Some(0));
+        assert!(!builder.undo());
+    }
+
+    #[test]
+    fn clicks_follow_the_mirrored_neck() {
+        let right = Builder::new(None, &Config::default());
+        // Row 1 is A; the nut is after "A [O]"
+        assert_eq!(right.hit(0, 1), Some(Hit::Nut(3)));
+        assert_eq!(right.hit(6, 1), Some(Hit::Fret(3, 1)));
+        assert_eq!(right.hit(6, 4), Some(Hit::Fret(0, 1)));
+
+        let mut config = Config::default();
+        config.left_handed = true;
+        let left = Builder::new(None, &config);
+        let (width, _) = left.fretboard().size();
+        assert_eq!(left.hit(0, 1), Some(Hit::Fret(3, NECK_FRETS)));
+        assert_eq!(left.hit(width - 7, 1), Some(Hit::Fret(3, 1)));
+        assert_eq!(left.hit(width - 1, 4), Some(Hit::Nut(0)));
+    }
+}
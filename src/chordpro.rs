@@ -0,0 +1,197 @@
+/// A song in ChordPro format: `{title: …}` directives, then lyrics with
+/// chords in brackets where they change, `[C]Twinkle twinkle [F]little star`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Song {
+    pub title: Option<String>,
+    pub subtitle: Option<String>,
+    pub artist: Option<String>,
+    pub key: Option<String>,
+    pub capo: Option<u8>,
+    pub lines: Vec<SongLine>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SongLine {
+    /// Lyrics split where the chord changes: each chord (empty before the
+    /// first) and the words sung over it
+    Lyrics(Vec<(String, String)>),
+    /// `{comment: …}`, printed but not sung
+    Comment(String),
+    /// `{start_of_chorus}` and the like, with the section's kind: "chorus",
+    /// "verse", "bridge", "tab"
+    Start(String),
+    /// The end of the current section
+    End,
+    /// An empty line between stanzas
+    Blank,
+}
+
+impl Song {
+    /// A song with no lyrics, only a title: how a typed progression is
+    /// exported.
+    pub fn titled(title: impl Into<String>) -> Song {
+        Song {
+            title: Some(title.into()),
+            ..Song::default()
+        }
+    }
+
+    /// Every chord the song uses, once each, in order of first appearance.
+    pub fn chords(&self) -> Vec<&str> {
+        let mut chords: Vec<&str> = Vec::new();
+        for line in &self.lines {
+            if let SongLine::Lyrics(segments) = line {
+                for (chord, _) in segments {
+                    if !chord.is_empty() && !chords.contains(&chord.as_str()) {
+                        chords.push(chord);
+                    }
+                }
+            }
+        }
+        chords
+    }
+}
+
+/// Read a ChordPro song. Unknown directives are skipped, as are `#` comment
+/// lines; nothing is an error.
+pub fn parse(text: &str) -> Song {
+    let mut song = Song::default();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(directive) = trimmed
+            .strip_prefix('{')
+            .and_then(|rest| rest.strip_suffix('}'))
+        {
+            let (name, value) = directive
+                .split_once([':', ' '])
+                .map_or((directive, ""), |(n, v)| (n, v.trim()));
+            let value = || Some(value.to_string()).filter(|v| !v.is_empty());
+            match name.trim().to_ascii_lowercase().as_str() {
+                "title" | "t" => song.title = value(),
+                "subtitle" | "st" => song.subtitle = value(),
+                "artist" => song.artist = value(),
+                "key" => song.key = value(),
+                "capo" => song.capo = value().and_then(|v| v.parse().ok()),
+                "comment" | "c" | "comment_italic" | "ci" | "comment_box" | "cb" => song
+                    .lines
+                    .push(SongLine::Comment(value().unwrap_or_default())),
+                "soc" => song.lines.push(SongLine::Start("chorus".into())),
+                "sov" => song.lines.push(SongLine::Start("verse".into())),
+                "sob" => song.lines.push(SongLine::Start("bridge".into())),
+                "sot" => song.lines.push(SongLine::Start("tab".into())),
+                "eoc" | "eov" | "eob" | "eot" => song.lines.push(SongLine::End),
+                name => {
+                    if let Some(kind) = name.strip_prefix("start_of_") {
+                        song.lines.push(SongLine::Start(kind.to_string()));
+                    } else if name.starts_with("end_of_") {
+                        song.lines.push(SongLine::End);
+                    }
+                }
+            }
+        } else if trimmed.is_empty() {
+            song.lines.push(SongLine::Blank);
+        } else {
+            song.lines.push(SongLine::Lyrics(segments(line.trim_end())));
+        }
+    }
+    song
+}
+
+/// Split a lyric line at each `[chord]`.
+fn segments(line: &str) -> Vec<(String, String)> {
+    let mut out = vec![(String::new(), String::new())];
+    let mut rest = line;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find(']').map(|i| open + i) else {
+            break;
+        };
+        out.last_mut()
+            .expect("never empty")
+            .1
+            .push_str(&rest[..open]);
+        out.push((rest[open + 1..close].trim().to_string(), String::new()));
+        rest = &rest[close + 1..];
+    }
+    out.last_mut().expect("never empty").1.push_str(rest);
+    if out[0].1.is_empty() && out.len() > 1 {
+        out.remove(0);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lyrics(pairs: &[(&str, &str)]) -> SongLine {
+        SongLine::Lyrics(
+            pairs
+                .iter()
+                .map(|&(chord, words)| (chord.to_string(), words.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn splits_lyrics_at_each_chord() {
+        assert_eq!(
+            segments("[C]Twinkle twinkle [F]little star"),
+            vec![
+                ("C".to_string(), "Twinkle twinkle ".to_string()),
+                ("F".to_string(), "little star".to_string()),
+            ]
+        );
+        // Words before the first chord are sung over no chord
+        assert_eq!(
+            segments("Oh [ G7 ]yes"),
+            vec![
+                (String::new(), "Oh ".to_string()),
+                ("G7".to_string(), "yes".to_string()),
+            ]
+        );
+        // An unclosed bracket is just text
+        assert_eq!(
+            segments("[Am]la [la"),
+            vec![("Am".to_string(), "la [la".to_string())]
+        );
+        assert_eq!(
+            segments("no chords"),
+            vec![(String::new(), "no chords".to_string())]
+        );
+    }
+
+    #[test]
+    fn reads_directives_and_sections() {
+        let song = parse(
+            "{title: Twinkle}\n{st: Traditional}\n{key: C}\n{capo: 2}\n# a note\n\
+             {c: slowly}\n{soc}\n[C]Twinkle [G/B]star\n{eoc}\n\n{start_of_bridge}\n{end_of_bridge}\n{x_unknown}\n",
+        );
+        assert_eq!(song.title.as_deref(), Some("Twinkle"));
+        assert_eq!(song.subtitle.as_deref(), Some("Traditional"));
+        assert_eq!(song.key.as_deref(), Some("C"));
+        assert_eq!(song.capo, Some(2));
+        assert_eq!(
+            song.lines,
+            vec![
+                SongLine::Comment("slowly".into()),
+                SongLine::Start("chorus".into()),
+                lyrics(&[("C", "Twinkle "), ("G/B", "star")]),
+                SongLine::End,
+                SongLine::Blank,
+                SongLine::Start("bridge".into()),
+                SongLine::End,
+            ]
+        );
+        assert_eq!(song.chords(), vec!["C", "G/B"]);
+    }
+
+    #[test]
+    fn lists_each_chord_once() {
+        let song = parse("[C]a [G]b\n[C]c [Am]d [G]e\n");
+        assert_eq!(song.chords(), vec!["C", "G", "Am"]);
+        assert!(Song::titled("C, G").chords().is_empty());
+    }
+}
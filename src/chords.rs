@@ -1,6 +1,26 @@
+use std::{fs, io, path::Path};
+
 use serde::{Deserialize, Serialize};
 
-const EMBEDDED_CHORDS: &str = include_str!("../chords.txt");
+use crate::theory::{self, Accidentals, PitchSet, Tone};
+
+/// File name of an external chord library.
+pub const LIBRARY_FILE: &str = "chords.txt";
+
+/// Header written at the top of a new library file.
+const NEW_LIBRARY_HEADER: &str = "\
+# uke-tui chord library: one `Name = G C E A` line per chord, X for a muted string.
+# Entries here override built-in chords of the same name.
+";
+
+/// The built-in chords, in library-file format.
+pub const EMBEDDED_CHORDS: &str = include_str!("../chords.txt");
+
+/// Fewest frets a diagram shows unless configured otherwise.
+pub const DEFAULT_FRET_WINDOW: u8 = 5;
+
+/// Frets on the neck; chord shapes never reach past the last one.
+pub const NECK_FRETS: u8 = 12;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Chord {
@@ -13,22 +33,44 @@ pub struct Chord {
 }
 
 impl Chord {
-    /// Load from an external file if present, otherwise use the embedded copy.
-    pub fn load_all() -> Vec<Chord> {
-        // Try external file first
-        let from_file = std::fs::read_to_string("chords.txt").ok();
-        let data = from_file.as_deref().unwrap_or(EMBEDDED_CHORDS);
-
-        data.lines()
-            .filter_map(|line| {
-                let line = line.trim();
-                if line.is_empty() || line.starts_with('#') {
-                    return None;
-                }
-                let (name, frets) = line.split_once('=')?;
-                Chord::from_string(name.trim(), frets.trim())
-            })
-            .collect()
+    /// Save this chord to a library file, replacing the line that defines the
+    /// same name or appending one. Comments, blank lines and the order of
+    /// everything else are kept; a missing file is created.
+    ///
+    /// Returns whether an existing entry was replaced.
+    pub fn save_to_library(&self, path: &Path) -> io::Result<bool> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => NEW_LIBRARY_HEADER.to_string(),
+            Err(e) => return Err(e),
+        };
+        let mut replaced = false;
+        let mut out = String::with_capacity(data.len() + 32);
+        for line in data.lines() {
+            let defines_self = !replaced
+                && !line.trim_start().starts_with('#')
+                && line
+                    .split_once('=')
+                    .is_some_and(|(name, _)| name.trim() == self.name);
+            if defines_self {
+                out.push_str(&self.to_line());
+                replaced = true;
+            } else {
+                out.push_str(line);
+            }
+            out.push('\n');
+        }
+        if !replaced {
+            out.push_str(&self.to_line());
+            out.push('\n');
+        }
+        if let Some(dir) = path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, out)?;
+        Ok(replaced)
     }
 
     /// Parse a line like `C#dim = 0 1 0 4`
@@ -49,6 +91,11 @@ impl Chord {
             };
         }
 
+        Self::new(name, frets)
+    }
+
+    /// Build a chord from a name and its frets, deriving the alias names.
+    pub fn new(name: String, frets: [Option<u8>; 4]) -> Option<Self> {
         // Extract root & type (e.g. "C#" + "dim")
         let (root, quality) = Self::split_name(&name)?;
 
@@ -66,6 +113,16 @@ impl Chord {
         })
     }
 
+    /// Format as a `chords.txt` line, e.g. `C#dim = 0 1 0 4`.
+    pub fn to_line(&self) -> String {
+        let frets: Vec<String> = self
+            .frets
+            .iter()
+            .map(|f| f.map_or("X".to_string(), |n| n.to_string()))
+            .collect();
+        format!("{} = {}", self.name, frets.join(" "))
+    }
+
     /// Inspect this chord’s frets and return (min_fret, max_fret), ignoring 0/Open and X/None.
     pub fn fret_bounds(&self) -> Option<(u8, u8)> {
         let used: Vec<u8> = self
@@ -85,62 +142,141 @@ impl Chord {
         }
     }
 
-    /// Check of this chord matches the user’s input (case-insensitive).
-    pub fn matches_name(&self, input: &str) -> bool {
-        if self.name.eq_ignore_ascii_case(input) {
-            true
-        } else {
-            self.alias_names
-                .iter()
-                .any(|alias| alias.eq_ignore_ascii_case(input))
+    /// Root pitch class and quality suffix, e.g. "C#m7" → (1, "m7").
+    pub fn root_and_quality(&self) -> Option<(u8, String)> {
+        let (root, quality) = Self::split_name(&self.name)?;
+        Some((theory::parse_note(&root)?, quality))
+    }
+
+    /// Pitch classes sounded by this shape in `tuning`.
+    pub fn pitch_set(&self, tuning: [u8; 4]) -> PitchSet {
+        let mut set = PitchSet::default();
+        for (string, fret) in self.frets.iter().enumerate() {
+            if let Some(f) = fret {
+                set.insert(pitch_class(tuning[string], *f));
+            }
         }
+        set
+    }
+
+    /// The official name followed by every alias.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str()).chain(self.alias_names.iter().map(String::as_str))
     }
 
-    /// Render this chord over exactly start..=end frets (all rows use the same window).
-    pub fn render_range(&self, start_fret: u8, end_fret: u8) -> String {
-        let strings = ["G", "C", "E", "A"];
-        let mut out = String::new();
+    /// Check of this chord matches the user’s input, ignoring case except
+    /// for a bare `M` or `m`: "am7" and "CMAJ7" match, but "CM7" is not "Cm7".
+    pub fn matches_name(&self, input: &str) -> bool {
+        self.names().any(|name| same_name(name, input))
+    }
 
-        // Title
-        out.push_str(&format!("Chord: {}\n", self.name));
+    /// The name matching `typed` (as `matches_name` does), so notes can be
+    /// spelled the way the user wrote the chord (Bb7, not A#7), or the
+    /// official name.
+    pub fn spelled_name(&self, typed: &str) -> &str {
+        self.names()
+            .find(|n| same_name(n, typed))
+            .unwrap_or(&self.name)
+    }
 
-        // Header indent + fret numbers
-        let prefix = "   "; // 3 spaces
-        out.push_str(prefix);
-        for f in start_fret..=end_fret {
-            out.push_str(&format!("{:>3}", f));
+    /// Each sounding string's note, spelled against `name` (one of this
+    /// chord's names, or its enharmonic twin where that avoids double
+    /// accidentals), and its interval above the root, in G C E A order.
+    /// Chords with an unknown quality spell by `accidentals` and name plain
+    /// intervals.
+    pub fn spelling(
+        &self,
+        name: &str,
+        tuning: [u8; 4],
+        accidentals: Accidentals,
+    ) -> [Option<(String, String)>; 4] {
+        let (root, quality) = theory::split_root(name).unwrap_or(("C", ""));
+        let formula = theory::quality_formula(quality).unwrap_or(&[]);
+        let root = theory::spelling_root(root, formula);
+        let mut out: [Option<(String, String)>; 4] = Default::default();
+        for (string, fret) in self.frets.iter().enumerate() {
+            if let Some(fret) = fret {
+                let pc = pitch_class(tuning[string], *fret);
+                out[string] = theory::spell_note(root, formula, pc, accidentals);
+            }
         }
-        out.push('\n');
+        out
+    }
 
-        // Divider line
-        let total_width = prefix.len() + ((end_fret - start_fret + 1) as usize) * 3;
-        out.push_str(&"-".repeat(total_width));
-        out.push('\n');
+    /// A suggested fingering (1 = index … 4 = little finger) for each
+    /// fretted string, in G C E A order. The chord files don't record
+    /// fingers, so this is a guess: an index barre when all four strings are
+    /// fretted and the lowest fret is shared, fingers in fret order
+    /// otherwise, with one or two notes placed one finger per fret from the
+    /// nut (C = 0 0 0 3 uses the ring finger).
+    pub fn fingers(&self) -> [Option<u8>; 4] {
+        let mut notes: Vec<(u8, usize)> = self
+            .frets
+            .iter()
+            .enumerate()
+            .filter_map(|(string, fret)| fret.filter(|&f| f > 0).map(|f| (f, string)))
+            .collect();
+        notes.sort_unstable();
+        let mut fingers = [None; 4];
+        let Some(&(lowest, _)) = notes.first() else {
+            return fingers;
+        };
+        let highest = notes.last().map_or(lowest, |n| n.0);
 
-        // Each string row (A E C G)
-        for &i in &[3, 2, 1, 0] {
-            let s = strings[i];
-            let fv = self.frets[i];
-            let ind = match fv {
-                Some(0) => 'O',
-                None => 'X',
-                _ => ' ',
-            };
-            // e.g. "G O| "
-            out.push_str(&format!("{} {}| ", s, ind));
+        let at_lowest = notes.iter().take_while(|n| n.0 == lowest).count();
+        let barre = notes.len() == 4 && at_lowest >= 2 && (highest > lowest || at_lowest == 4);
+        let rest = if barre {
+            &notes[at_lowest..]
+        } else {
+            &notes[..]
+        };
+        if barre {
+            for &(_, string) in &notes[..at_lowest] {
+                fingers[string] = Some(1);
+            }
+        }
 
-            // Cells
-            for f in start_fret..=end_fret {
-                if fv == Some(f) {
-                    out.push_str("●  ");
+        // Where the first finger goes, leaving room for the notes after it
+        let needed = rest.len() as u8;
+        let base = if highest <= 4 { 1 } else { lowest };
+        let mut previous = (u8::from(barre), lowest);
+        for (n, &(fret, string)) in rest.iter().enumerate() {
+            let finger = if n == 0 && !barre {
+                if needed <= 2 {
+                    (fret - base + 1).min(5 - needed)
                 } else {
-                    out.push_str("-  ");
+                    1
                 }
-            }
-            out.push('\n');
+            } else {
+                // The next finger, or further along if frets were skipped
+                (previous.0 + 1).max(previous.0 + fret - previous.1)
+            };
+            previous = (finger.min(4), fret);
+            fingers[string] = Some(previous.0);
         }
+        fingers
+    }
 
-        out
+    /// The role each string's note plays in this chord, in G C E A order.
+    /// Muted strings, and every string of a chord with an unknown quality,
+    /// are `Tone::Other`.
+    pub fn tones(&self, tuning: [u8; 4]) -> [Tone; 4] {
+        let mut tones = [Tone::Other; 4];
+        let Some((root, quality)) = self.root_and_quality() else {
+            return tones;
+        };
+        let formula = theory::quality_formula(&quality).unwrap_or(&[]);
+        for (string, fret) in self.frets.iter().enumerate() {
+            let Some(fret) = fret else { continue };
+            let semitones = (pitch_class(tuning[string], *fret) + 12 - root) % 12;
+            if let Some(degree) = formula
+                .iter()
+                .find(|d| theory::degree_semitones(d) == Some(semitones))
+            {
+                tones[string] = Tone::of_degree(degree);
+            }
+        }
+        tones
     }
 
     // ──────────────── Helper functions ────────────────
@@ -148,21 +284,7 @@ impl Chord {
     /// Split a chord into note + type. "C#dim" → ("C#", "dim")
     fn split_name(name: &str) -> Option<(String, String)> {
         // Try the 2-char roots first, then single letters
-        let roots = [
-            "A#", "Bb", "C#", "Db", "D#", "Eb", "F#", "Gb", "G#", "Ab", "A", "B", "C", "D", "E",
-            "F", "G",
-        ];
-        for &r in &roots {
-            // if name.starts_with(r) {
-            //     let qual = name[r.len()..].to_string();
-            //     return Some((r.to_string(), qual));
-            // }
-            if let Some(stripped) = name.strip_prefix(r) {
-                let qual = stripped.to_string();
-                return Some((r.to_string(), qual));
-            }
-        }
-        None
+        theory::split_root(name).map(|(root, qual)| (root.to_string(), qual.to_string()))
     }
 
     /// For a given root, list its equivalents
@@ -182,3 +304,220 @@ impl Chord {
         }
     }
 }
+
+/// Fret window shared by a set of chords: from fret 1 when any string is open
+/// or fretted low, otherwise from the lowest fret, and at least `min_frets` wide.
+pub fn fret_window<'a>(chords: impl IntoIterator<Item = &'a Chord>, min_frets: u8) -> (u8, u8) {
+    let mut gmin = u8::MAX;
+    let mut gmax = 0u8;
+    let mut has_open = false;
+    for chord in chords {
+        if chord.frets.contains(&Some(0)) {
+            has_open = true;
+        }
+        if let Some((mn, mx)) = chord.fret_bounds() {
+            gmin = gmin.min(mn);
+            gmax = gmax.max(mx);
+        }
+    }
+    // No fretted notes at all (or only messages) starts at the nut too
+    let start = if has_open || gmin < 2 || gmax == 0 {
+        1
+    } else {
+        gmin
+    };
+    let end = std::cmp::max(gmax, start + min_frets.max(1) - 1);
+    (start, end)
+}
+
+/// What parsing one library file found.
+#[derive(Debug, Clone, Default)]
+pub struct LibraryFile {
+    pub chords: Vec<Chord>,
+    /// Numbers of lines that didn't parse
+    pub bad_lines: Vec<usize>,
+    /// Numbers of lines fretted past the neck, and the first fret too far
+    pub past_neck: Vec<(usize, u8)>,
+}
+
+/// Parse library text: one `Name = f f f f` per line, `#` comments and blank
+/// lines ignored. Chords fretted past the end of the neck are left out.
+pub fn parse_library(data: &str) -> LibraryFile {
+    let mut parsed = LibraryFile::default();
+    for (n, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line
+            .split_once('=')
+            .and_then(|(name, frets)| Chord::from_string(name.trim(), frets.trim()))
+        {
+            Some(chord) => match off_neck(&chord.frets) {
+                Some(fret) => parsed.past_neck.push((n + 1, fret)),
+                None => parsed.chords.push(chord),
+            },
+            None => parsed.bad_lines.push(n + 1),
+        }
+    }
+    parsed
+}
+
+/// The built-in chords, without any library files layered over them.
+pub fn embedded() -> Vec<Chord> {
+    parse_library(EMBEDDED_CHORDS).chords
+}
+
+/// The first fret in `frets` past the end of the neck.
+pub fn off_neck(frets: &[Option<u8>; 4]) -> Option<u8> {
+    frets.iter().flatten().copied().find(|&f| f > NECK_FRETS)
+}
+
+/// Why a shape with `fret` in it is refused.
+pub fn past_neck(fret: u8) -> String {
+    format!(
+        "Fret {} is past the end of the neck ({} frets)",
+        fret, NECK_FRETS
+    )
+}
+
+/// Pitch class of `fret` on a string tuned to `open`, without overflowing
+/// whatever the fret.
+fn pitch_class(open: u8, fret: u8) -> u8 {
+    ((u16::from(open) + u16::from(fret)) % 12) as u8
+}
+
+/// Quality words whose case doesn't matter, unlike a bare `M` or `m`.
+const QUALITY_WORDS: [&str; 6] = ["maj", "min", "dim", "aug", "sus", "add"];
+
+/// Whether `input` names the chord called `name`. Case is ignored except for
+/// an `M` or `m` that isn't part of a word like "maj" or "dim", as those
+/// mean major and minor: "dB", "cMaj7" and "GSUS4" name Db, Cmaj7 and Gsus4,
+/// but "CM7" doesn't name Cm7.
+fn same_name(name: &str, input: &str) -> bool {
+    let root = |s: &str| match s.char_indices().nth(1) {
+        Some((i, '#' | 'b' | 'B')) => i + 1,
+        Some((i, _)) => i,
+        None => s.len(),
+    };
+    let (a, b) = (root(name), root(input));
+    name[..a].eq_ignore_ascii_case(&input[..b]) && same_quality(&name[a..], &input[b..])
+}
+
+/// Compare qualities word by word: `QUALITY_WORDS` in any case, everything
+/// else exactly.
+fn same_quality(mut name: &str, mut input: &str) -> bool {
+    let starts_with = |s: &str, word: &str| {
+        s.get(..word.len())
+            .is_some_and(|h| h.eq_ignore_ascii_case(word))
+    };
+    while let Some(c) = name.chars().next() {
+        let len = match QUALITY_WORDS.iter().find(|w| starts_with(name, w)) {
+            Some(word) if starts_with(input, word) => word.len(),
+            Some(_) => return false,
+            None if input.starts_with(c) => c.len_utf8(),
+            None => return false,
+        };
+        name = &name[len..];
+        input = &input[len..];
+    }
+    input.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theory::STANDARD_TUNING;
+
+    #[test]
+    fn names_ignore_case_but_for_major_and_minor() {
+        for (name, input) in [
+            ("Cmaj7", "CMAJ7"),
+            ("Cmaj7", "cmaj7"),
+            ("Cmaj7", "CMaj7"),
+            ("Db", "DB"),
+            ("Db", "db"),
+            ("C#m7", "c#m7"),
+            ("Am7", "am7"),
+            ("Gsus4", "GSUS4"),
+            ("Cdim7", "cDim7"),
+            ("CmM7", "cmM7"),
+            ("C", "c"),
+        ] {
+            assert!(same_name(name, input), "{} should match {}", input, name);
+        }
+        for (name, input) in [
+            ("Cm7", "CM7"),
+            ("Am7", "AM7"),
+            ("Am", "AM"),
+            ("CmM7", "CMM7"),
+            ("Cmaj7", "Cmaj"),
+            ("C", "Cm"),
+            ("Db", "D"),
+            ("B", "Bb"),
+            ("C", ""),
+        ] {
+            assert!(
+                !same_name(name, input),
+                "{} shouldn't match {}",
+                input,
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn aliases_match_too() {
+        let chord = Chord::new("C#m".into(), [Some(1), Some(1), Some(0), Some(4)]).unwrap();
+        assert!(chord.matches_name("DBm"));
+        assert_eq!(chord.spelled_name("dbm"), "Dbm");
+        assert_eq!(chord.spelled_name("C#M"), "C#m");
+    }
+
+    #[test]
+    fn spells_from_the_twin_root_to_avoid_double_sharps() {
+        let chord = Chord::new("A#7".into(), [Some(1), Some(2), Some(1), Some(1)]).unwrap();
+        let notes = |name: &str| -> Vec<String> {
+            chord
+                .spelling(name, STANDARD_TUNING, Accidentals::Sharps)
+                .into_iter()
+                .map(|n| n.map(|(note, _)| note).unwrap_or_default())
+                .collect()
+        };
+        // G C E A strings: Ab, D, F, Bb
+        assert_eq!(notes("A#7"), ["Ab", "D", "F", "Bb"]);
+        assert_eq!(notes("Bb7"), ["Ab", "D", "F", "Bb"]);
+        let f_sharp = Chord::new("F#".into(), [Some(3), Some(1), Some(2), Some(1)]).unwrap();
+        let spelled = f_sharp.spelling("F#", STANDARD_TUNING, Accidentals::Flats);
+        let notes: Vec<&str> = spelled.iter().flatten().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(notes, ["A#", "C#", "F#", "A#"]);
+    }
+
+    #[test]
+    fn out_of_range_frets_do_not_overflow() {
+        let chord = Chord::new("C".into(), [Some(0), Some(0), Some(0), Some(250)]).unwrap();
+        assert_eq!(off_neck(&chord.frets), Some(250));
+        // A4 (69) + 250 = 319, pitch class 7: G, the fifth
+        assert_eq!(chord.tones(STANDARD_TUNING)[3], Tone::of_degree("5"));
+        assert!(chord.pitch_set(STANDARD_TUNING).contains(7));
+        let spelled = chord.spelling("C", STANDARD_TUNING, Accidentals::Sharps);
+        assert_eq!(spelled[3], Some(("G".to_string(), "5".to_string())));
+    }
+
+    #[test]
+    fn library_files_skip_comments_and_report_bad_lines() {
+        let parsed = parse_library("# comment\n\nC = 0 0 0 3\nAm=2 0 0 0\nC =\nD = 2 2 2 14\n");
+        let names: Vec<&str> = parsed.chords.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["C", "Am"]);
+        assert_eq!(parsed.bad_lines, [5]);
+        assert_eq!(parsed.past_neck, [(6, 14)]);
+    }
+
+    #[test]
+    fn the_embedded_chords_all_parse() {
+        let parsed = parse_library(EMBEDDED_CHORDS);
+        assert!(parsed.bad_lines.is_empty() && parsed.past_neck.is_empty());
+        assert_eq!(embedded().len(), parsed.chords.len());
+        assert!(embedded().iter().any(|c| c.name == "C"));
+    }
+}
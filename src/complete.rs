@@ -0,0 +1,143 @@
+use std::ops::Range;
+
+use uke_tui::chords::Chord;
+
+/// Searchable index of every chord name and alias in the library.
+pub struct Completer {
+    names: Vec<String>,
+}
+
+impl Completer {
+    /// Build the index from the loaded chords.
+    pub fn new(chords: &[Chord]) -> Self {
+        let mut names: Vec<String> = chords
+            .iter()
+            .flat_map(|c| c.names().map(str::to_string))
+            .collect();
+        names.sort();
+        names.dedup();
+        Self { names }
+    }
+
+    /// Names matching `word`: prefix matches first (shortest first), then fuzzy
+    /// subsequence matches ordered by how tightly the letters cluster.
+    pub fn candidates(&self, word: &str, limit: usize) -> Vec<String> {
+        if word.is_empty() {
+            return Vec::new();
+        }
+        let mut prefix: Vec<&String> = Vec::new();
+        let mut fuzzy: Vec<(usize, &String)> = Vec::new();
+        for name in &self.names {
+            if starts_with_ignore_case(name, word) {
+                prefix.push(name);
+            } else if let Some(spread) = subsequence_spread(name, word) {
+                fuzzy.push((spread, name));
+            }
+        }
+        // Prefer an exact-case prefix ("Am" before "AM…") when the input has one
+        prefix.sort_by_key(|n| (!n.starts_with(word), n.len(), n.to_string()));
+        fuzzy.sort_by_key(|&(spread, n)| (spread, n.len(), n.to_string()));
+        prefix
+            .into_iter()
+            .chain(fuzzy.into_iter().map(|(_, n)| n))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Byte range of the comma-separated entry containing `cursor`, without
+/// surrounding whitespace; completion only ever replaces this range.
+pub fn current_entry(input: &str, cursor: usize) -> Range<usize> {
+    let start = input[..cursor].rfind(',').map_or(0, |i| i + 1);
+    let end = input[cursor..]
+        .find(',')
+        .map_or(input.len(), |i| cursor + i);
+    let entry = &input[start..end];
+    let lead = entry.len() - entry.trim_start().len();
+    let trail = entry.len() - entry.trim_end().len();
+    (start + lead)..(end - trail).max(start + lead)
+}
+
+fn starts_with_ignore_case(name: &str, word: &str) -> bool {
+    name.get(..word.len())
+        .is_some_and(|head| head.eq_ignore_ascii_case(word))
+}
+
+/// If every char of `word` appears in order in `name` (ignoring case), return
+/// the length of the stretch of `name` they span.
+fn subsequence_spread(name: &str, word: &str) -> Option<usize> {
+    let mut wanted = word.chars().map(|c| c.to_ascii_lowercase()).peekable();
+    let mut first = None;
+    for (i, c) in name.chars().enumerate() {
+        match wanted.peek() {
+            Some(&w) if w == c.to_ascii_lowercase() => {
+                first.get_or_insert(i);
+                wanted.next();
+                if wanted.peek().is_none() {
+                    return Some(i + 1 - first.unwrap_or(0));
+                }
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completer() -> Completer {
+        let chords: Vec<Chord> = ["C", "C7", "Cm", "Cm7", "Cmaj7", "A#7", "Am"]
+            .iter()
+            .map(|name| Chord::new(name.to_string(), [Some(0); 4]).unwrap())
+            .collect();
+        Completer::new(&chords)
+    }
+
+    #[test]
+    fn prefix_matches_come_before_fuzzy_ones() {
+        let completer = completer();
+        // "Cm7" spreads "C…7" over three letters, "Cmaj7" over five
+        assert_eq!(completer.candidates("C7", 10), ["C7", "Cm7", "Cmaj7"]);
+        assert_eq!(completer.candidates("am", 10), ["Am"]);
+        assert!(completer.candidates("", 10).is_empty());
+        assert!(completer.candidates("Dm", 10).is_empty());
+    }
+
+    #[test]
+    fn aliases_complete_too() {
+        let completer = completer();
+        assert_eq!(completer.candidates("Bb", 10), ["Bb7"]);
+        assert_eq!(completer.candidates("a#", 10), ["A#7"]);
+    }
+
+    #[test]
+    fn candidates_stop_at_the_limit() {
+        let completer = completer();
+        assert_eq!(completer.candidates("C", 2), ["C", "C7"]);
+        assert_eq!(completer.candidates("C", 5).len(), 5);
+        assert!(completer.candidates("C", 0).is_empty());
+    }
+
+    #[test]
+    fn current_entry_is_the_one_under_the_cursor() {
+        let input = "C, Am ,G";
+        // Inside the first entry, on its comma and after it
+        assert_eq!(current_entry(input, 0), 0..1);
+        assert_eq!(current_entry(input, 1), 0..1);
+        assert_eq!(current_entry(input, 2), 3..5);
+        // Trailing space is left out, even with the cursor on it
+        assert_eq!(current_entry(input, 6), 3..5);
+        assert_eq!(current_entry(input, 7), 7..8);
+        assert_eq!(current_entry(input, 8), 7..8);
+        assert_eq!(current_entry("  Am  ", 6), 2..4);
+
+        // Empty entries are empty ranges where they stand
+        assert_eq!(current_entry("C,,G", 2), 2..2);
+        assert_eq!(current_entry("C,  ,G", 3), 4..4);
+        assert_eq!(current_entry("", 0), 0..0);
+    }
+}
@@ -0,0 +1,296 @@
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use serde::{Deserialize, Deserializer, de::Error as _};
+
+use uke_tui::chords::{DEFAULT_FRET_WINDOW, LIBRARY_FILE};
+use uke_tui::midi::{self, Arrangement};
+use uke_tui::theme::{THEME_NAMES, Theme};
+use uke_tui::theory::{self, Accidentals};
+use uke_tui::widgets::{Labels, Orientation, StringOrder, View};
+
+use crate::keys::{KeyBindings, KeyList, KeyOverrides, Preset};
+use crate::library;
+use crate::paths;
+
+/// Name of the settings file inside the config directory.
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Settings read from `$XDG_CONFIG_HOME/uke-tui/config.toml`. Every key is
+/// optional; a missing file means all defaults.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Library files layered over the built-in chords, in order; a later file
+    /// overrides earlier ones chord by chord
+    #[serde(deserialize_with = "de_paths")]
+    pub library_paths: Vec<PathBuf>,
+    /// Library file that chords saved from the builder are written to
+    #[serde(deserialize_with = "de_path")]
+    pub save_to: PathBuf,
+    /// Open-string pitches as MIDI notes, G C E A order
+    #[serde(deserialize_with = "de_tuning")]
+    pub tuning: [u8; 4],
+    /// Spelling for notes named outside a chord (sharps or flats)
+    pub accidentals: Accidentals,
+    /// Fewest frets a diagram shows
+    #[serde(deserialize_with = "de_fret_window")]
+    pub fret_window: u8,
+    /// Columns between diagrams in the grid
+    pub grid_spacing: usize,
+    /// Strings as rows ("horizontal") or as columns in chord boxes ("vertical")
+    pub orientation: Orientation,
+    /// Mirror every diagram for left-handed players
+    pub left_handed: bool,
+    /// Which string horizontal diagrams put on top
+    pub string_order: StringOrder,
+    /// What the dots show: "dots", "notes", "intervals" or "fingers"
+    pub labels: Labels,
+    /// Colour scheme, one of `THEME_NAMES`
+    #[serde(deserialize_with = "de_theme")]
+    pub theme: Theme,
+    /// Key binding preset: "default", "vi" or "emacs"
+    pub keymap: Preset,
+    /// Tempo, time signature, bars per chord and strum offset of MIDI
+    /// exports, from `[midi]`
+    #[serde(deserialize_with = "de_midi")]
+    pub midi: Arrangement,
+    /// Actions whose preset keys are replaced, from `[keys]`
+    #[serde(rename = "keys", deserialize_with = "de_keys")]
+    key_overrides: KeyOverrides,
+    /// The preset with the overrides applied
+    #[serde(skip)]
+    pub keys: KeyBindings,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        // System, then user, then the project in the working directory
+        let user = paths::config_dir().map(|dir| dir.join(LIBRARY_FILE));
+        let mut library_paths = vec![paths::system_config_dir().join(LIBRARY_FILE)];
+        library_paths.extend(user.clone());
+        library_paths.push(PathBuf::from(LIBRARY_FILE));
+        Self {
+            library_paths,
+            save_to: user.unwrap_or_else(|| PathBuf::from(LIBRARY_FILE)),
+            tuning: theory::STANDARD_TUNING,
+            accidentals: Accidentals::default(),
+            fret_window: DEFAULT_FRET_WINDOW,
+            grid_spacing: 2,
+            orientation: Orientation::default(),
+            left_handed: false,
+            string_order: StringOrder::default(),
+            labels: Labels::default(),
+            theme: Theme::default(),
+            keymap: Preset::default(),
+            midi: Arrangement::default(),
+            key_overrides: KeyOverrides::default(),
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Read the config file. A broken file is reported and ignored, so a typo
+    /// never stops the app from starting. `NO_COLOR` overrides the theme.
+    pub fn load() -> (Config, Option<String>) {
+        let (mut config, warning) = Self::read();
+        if Theme::no_color() {
+            config.theme = Theme::mono();
+        }
+        (config, warning)
+    }
+
+    fn read() -> (Config, Option<String>) {
+        let Some(path) = paths::config_dir().map(|dir| dir.join(CONFIG_FILE)) else {
+            return (Config::default(), None);
+        };
+        let data = match fs::read_to_string(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return (Config::default(), None),
+            Err(e) => {
+                return (
+                    Config::default(),
+                    Some(format!("{}: {}", path.display(), e)),
+                );
+            }
+        };
+        match Config::parse(&data) {
+            Ok(config) => (config, None),
+            Err(e) => (Config::default(), Some(format!("{}{}", path.display(), e))),
+        }
+    }
+
+    /// Settings from the text of a config file, or what is wrong with it,
+    /// starting with the line it is on: " line 3: unknown field …".
+    fn parse(data: &str) -> Result<Config, String> {
+        match toml::from_str::<Config>(data) {
+            Ok(mut config) => {
+                config.keys = KeyBindings::new(config.keymap, &config.key_overrides);
+                Ok(config)
+            }
+            Err(e) => {
+                let line = e
+                    .span()
+                    .map(|span| format!(" line {}", data[..span.start].matches('\n').count() + 1))
+                    .unwrap_or_default();
+                Err(format!("{}: {}", line, e.message()))
+            }
+        }
+    }
+
+    /// How diagrams are laid out.
+    pub fn view(&self) -> View {
+        View {
+            orientation: self.orientation,
+            left_handed: self.left_handed,
+            string_order: self.string_order,
+            labels: self.labels,
+        }
+    }
+
+    /// Every library file to layer, in order: `library-paths`, then `save-to`
+    /// unless it is already listed (so saved chords always load), then `extra`.
+    pub fn library_layers(&self, extra: &[PathBuf]) -> Vec<PathBuf> {
+        let mut layers = self.library_paths.clone();
+        if !layers.iter().any(|p| library::same_file(p, &self.save_to)) {
+            layers.push(self.save_to.clone());
+        }
+        layers.extend_from_slice(extra);
+        layers
+    }
+}
+
+/// Parse a tuning: "standard", "low-g", or four pitches like "G4 C4 E4 A4".
+fn parse_tuning(s: &str) -> Option<[u8; 4]> {
+    match s.trim() {
+        "standard" => return Some(theory::STANDARD_TUNING),
+        "low-g" => return Some([55, 60, 64, 69]),
+        _ => {}
+    }
+    let pitches: Vec<u8> = s
+        .split_whitespace()
+        .map(theory::parse_pitch)
+        .collect::<Option<_>>()?;
+    pitches.try_into().ok()
+}
+
+fn de_paths<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<PathBuf>, D::Error> {
+    let paths = Vec::<String>::deserialize(d)?;
+    Ok(paths.iter().map(|p| paths::expand_home(p)).collect())
+}
+
+fn de_path<'de, D: Deserializer<'de>>(d: D) -> Result<PathBuf, D::Error> {
+    Ok(paths::expand_home(&String::deserialize(d)?))
+}
+
+fn de_tuning<'de, D: Deserializer<'de>>(d: D) -> Result<[u8; 4], D::Error> {
+    let s = String::deserialize(d)?;
+    parse_tuning(&s).ok_or_else(|| {
+        D::Error::custom(format!(
+            "invalid tuning `{}`, expected \"standard\", \"low-g\" or four notes like \"G4 C4 E4 A4\"",
+            s
+        ))
+    })
+}
+
+fn de_fret_window<'de, D: Deserializer<'de>>(d: D) -> Result<u8, D::Error> {
+    match u8::deserialize(d)? {
+        n @ 1..=12 => Ok(n),
+        n => Err(D::Error::custom(format!(
+            "fret-window must be between 1 and 12, got {}",
+            n
+        ))),
+    }
+}
+
+fn de_theme<'de, D: Deserializer<'de>>(d: D) -> Result<Theme, D::Error> {
+    let name = String::deserialize(d)?;
+    Theme::named(&name).ok_or_else(|| {
+        D::Error::custom(format!(
+            "unknown theme `{}`, expected one of {}",
+            name,
+            THEME_NAMES.join(", ")
+        ))
+    })
+}
+
+/// The `[midi]` table; missing keys keep their defaults.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct MidiTable {
+    tempo: Option<u32>,
+    time_signature: Option<String>,
+    bars_per_chord: Option<u32>,
+    strum_offset: Option<u32>,
+}
+
+fn de_midi<'de, D: Deserializer<'de>>(d: D) -> Result<Arrangement, D::Error> {
+    let table = MidiTable::deserialize(d)?;
+    let mut arrangement = Arrangement::default();
+    if let Some(tempo) = table.tempo {
+        if !midi::TEMPO_RANGE.contains(&tempo) {
+            return Err(D::Error::custom(format!(
+                "midi tempo must be between {} and {}, got {}",
+                midi::TEMPO_RANGE.start(),
+                midi::TEMPO_RANGE.end(),
+                tempo
+            )));
+        }
+        arrangement.tempo = tempo;
+    }
+    if let Some(text) = table.time_signature {
+        arrangement.time_signature = midi::parse_time_signature(&text).map_err(D::Error::custom)?;
+    }
+    if let Some(bars) = table.bars_per_chord {
+        if bars == 0 {
+            return Err(D::Error::custom("midi bars-per-chord must be at least 1"));
+        }
+        arrangement.bars = bars;
+    }
+    if let Some(offset) = table.strum_offset {
+        if offset > midi::MAX_STRUM_OFFSET {
+            return Err(D::Error::custom(format!(
+                "midi strum-offset must be at most {} milliseconds, got {}",
+                midi::MAX_STRUM_OFFSET,
+                offset
+            )));
+        }
+        arrangement.strum_offset = offset;
+    }
+    Ok(arrangement)
+}
+
+fn de_keys<'de, D: Deserializer<'de>>(d: D) -> Result<KeyOverrides, D::Error> {
+    let table = BTreeMap::<String, KeyList>::deserialize(d)?;
+    KeyOverrides::from_table(&table).map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_the_line_of_a_bad_key() {
+        let error = Config::parse("theme = \"dark\"\ngrid-spacing = 3\ncolour = \"red\"\n")
+            .expect_err("unknown key");
+        assert!(
+            error.starts_with(" line 3: unknown field `colour`"),
+            "{}",
+            error
+        );
+
+        let error = Config::parse("nonsense = 1").expect_err("unknown key");
+        assert!(error.starts_with(" line 1: "), "{}", error);
+
+        let error = Config::parse("left-handed = true\n\n[midi]\ntempo = \"fast\"\n")
+            .expect_err("bad value");
+        assert!(error.starts_with(" line 4: "), "{}", error);
+    }
+
+    #[test]
+    fn reads_settings() {
+        let config = Config::parse("grid-spacing = 3\nleft-handed = true\n").expect("parses");
+        assert_eq!(config.grid_spacing, 3);
+        assert!(config.left_handed);
+    }
+}
@@ -0,0 +1,353 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use crate::paths;
+
+/// Most history entries kept in memory and on disk; the history file is
+/// rewritten without the oldest entries once it grows past this.
+const HISTORY_LIMIT: usize = 500;
+
+/// Single-line text editor with a cursor and a persistent input history.
+#[derive(Default)]
+pub struct LineEditor {
+    text: String,
+    /// Byte offset of the cursor, always on a char boundary
+    cursor: usize,
+    history: Vec<String>,
+    /// Position while browsing history with Ctrl-P/Ctrl-N
+    history_pos: Option<usize>,
+    /// What was being typed before history browsing started
+    draft: String,
+    history_file: Option<PathBuf>,
+}
+
+impl LineEditor {
+    /// Create an editor whose history is loaded from, and appended to, the history file.
+    pub fn with_history() -> Self {
+        Self::with_history_file(paths::state_dir().map(|dir| dir.join("history")))
+    }
+
+    fn with_history_file(history_file: Option<PathBuf>) -> Self {
+        let mut history: Vec<String> = history_file
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|data| data.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        let excess = history.len().saturating_sub(HISTORY_LIMIT);
+        history.drain(..excess);
+        let editor = Self {
+            history,
+            history_file,
+            ..Self::default()
+        };
+        if excess > 0 {
+            editor.save_history();
+        }
+        editor
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replace the whole line and put the cursor at the end.
+    pub fn set_text(&mut self, text: String) {
+        self.cursor = text.len();
+        self.text = text;
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+        self.history_pos = None;
+    }
+
+    /// Replace a byte range and leave the cursor just after the new text.
+    pub fn replace_range(&mut self, range: std::ops::Range<usize>, with: &str) {
+        self.cursor = range.start + with.len();
+        self.text.replace_range(range, with);
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Insert pasted text; line breaks become chord separators.
+    pub fn insert_str(&mut self, s: &str) {
+        let flat = s
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.text.insert_str(self.cursor, &flat);
+        self.cursor += flat.len();
+    }
+
+    /// Delete the char before the cursor.
+    pub fn backspace(&mut self) {
+        let start = self.prev_boundary(self.cursor);
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Delete the char under the cursor.
+    pub fn delete(&mut self) {
+        let end = self.next_boundary(self.cursor);
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    pub fn left(&mut self) {
+        self.cursor = self.prev_boundary(self.cursor);
+    }
+
+    pub fn right(&mut self) {
+        self.cursor = self.next_boundary(self.cursor);
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    pub fn word_left(&mut self) {
+        self.cursor = self.word_start(self.cursor);
+    }
+
+    pub fn word_right(&mut self) {
+        let rest = &self.text[self.cursor..];
+        let skip = rest.find(|c| !is_separator(c)).unwrap_or(rest.len());
+        let word = rest[skip..].find(is_separator).unwrap_or(rest.len() - skip);
+        self.cursor += skip + word;
+    }
+
+    /// Ctrl-U: delete everything before the cursor.
+    pub fn kill_to_start(&mut self) {
+        self.text.replace_range(..self.cursor, "");
+        self.cursor = 0;
+    }
+
+    /// Ctrl-K: delete everything from the cursor on.
+    pub fn kill_to_end(&mut self) {
+        self.text.truncate(self.cursor);
+    }
+
+    /// Ctrl-W: delete the word before the cursor.
+    pub fn kill_word(&mut self) {
+        let start = self.word_start(self.cursor);
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Record a submitted line, skipping blanks and immediate repeats.
+    pub fn push_history(&mut self, line: &str) {
+        self.history_pos = None;
+        let line = line.trim();
+        if line.is_empty() || self.history.last().is_some_and(|l| l == line) {
+            return;
+        }
+        self.history.push(line.to_string());
+        if self.history.len() > HISTORY_LIMIT {
+            self.history.remove(0);
+            self.save_history();
+            return;
+        }
+        // Persisting history is best effort; a read-only home shouldn't stop lookups.
+        if let Some(path) = &self.history_file {
+            if let Some(dir) = path.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+            if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+                let _ = writeln!(file, "{}", line);
+            }
+        }
+    }
+
+    /// Rewrite the history file with just the entries in memory, dropping
+    /// those past `HISTORY_LIMIT`. Best effort, like appending.
+    fn save_history(&self) {
+        let Some(path) = &self.history_file else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let mut data = self.history.join("\n");
+        data.push('\n');
+        let _ = fs::write(path, data);
+    }
+
+    /// Ctrl-P: step back to the previous history entry.
+    pub fn history_prev(&mut self) {
+        let pos = match self.history_pos {
+            Some(0) => return,
+            Some(i) => i - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = self.text.clone();
+                self.history.len() - 1
+            }
+        };
+        self.history_pos = Some(pos);
+        self.set_text(self.history[pos].clone());
+    }
+
+    /// Ctrl-N: step forward again, ending at the line being typed.
+    pub fn history_next(&mut self) {
+        let Some(i) = self.history_pos else {
+            return;
+        };
+        if i + 1 < self.history.len() {
+            self.history_pos = Some(i + 1);
+            self.set_text(self.history[i + 1].clone());
+        } else {
+            self.history_pos = None;
+            let draft = std::mem::take(&mut self.draft);
+            self.set_text(draft);
+        }
+    }
+
+    fn prev_boundary(&self, i: usize) -> usize {
+        self.text[..i]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(j, _)| j)
+    }
+
+    fn next_boundary(&self, i: usize) -> usize {
+        self.text[i..]
+            .chars()
+            .next()
+            .map_or(i, |c| i + c.len_utf8())
+    }
+
+    /// Start of the word before `i`, skipping separators first.
+    fn word_start(&self, i: usize) -> usize {
+        let before = self.text[..i].trim_end_matches(is_separator);
+        before.rfind(is_separator).map_or(0, |j| {
+            j + before[j..].chars().next().map_or(1, char::len_utf8)
+        })
+    }
+}
+
+/// Words are chord names, so commas split them as well as spaces.
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || c == ','
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> LineEditor {
+        let mut editor = LineEditor::default();
+        editor.set_text(text.to_string());
+        editor
+    }
+
+    #[test]
+    fn moves_by_words_split_on_commas_and_spaces() {
+        let mut e = editor("C, Am7,  F#m");
+        e.word_left();
+        assert_eq!(e.cursor(), 9);
+        e.word_left();
+        assert_eq!(e.cursor(), 3);
+        e.word_left();
+        assert_eq!(e.cursor(), 0);
+        e.word_left();
+        assert_eq!(e.cursor(), 0);
+        e.word_right();
+        assert_eq!(e.cursor(), 1);
+        e.word_right();
+        assert_eq!(e.cursor(), 6);
+        e.word_right();
+        assert_eq!(e.cursor(), 12);
+        e.word_right();
+        assert_eq!(e.cursor(), 12);
+    }
+
+    #[test]
+    fn kills_the_word_before_the_cursor() {
+        let mut e = editor("C, Am7, ");
+        e.kill_word();
+        assert_eq!((e.text(), e.cursor()), ("C, ", 3));
+        e.kill_word();
+        assert_eq!((e.text(), e.cursor()), ("", 0));
+
+        let mut e = editor("Bb7 Ébm");
+        e.left();
+        e.kill_word();
+        assert_eq!((e.text(), e.cursor()), ("Bb7 m", 4));
+    }
+
+    #[test]
+    fn pastes_lines_as_chord_separators() {
+        let mut e = editor("C, ");
+        e.home();
+        e.insert_str("G\n\n  Am \r\nF");
+        assert_eq!((e.text(), e.cursor()), ("G, Am, FC, ", 8));
+        e.end();
+        e.insert_str("é");
+        assert_eq!(e.cursor(), e.text().len());
+    }
+
+    #[test]
+    fn recalls_history_and_the_draft() {
+        let mut e = LineEditor::default();
+        for line in ["C", "  ", "G", "G", "Am"] {
+            e.push_history(line);
+        }
+        assert_eq!(e.history, ["C", "G", "Am"]);
+        e.set_text("F".into());
+        e.history_prev();
+        assert_eq!(e.text(), "Am");
+        e.history_prev();
+        e.history_prev();
+        e.history_prev();
+        assert_eq!(e.text(), "C");
+        e.history_next();
+        assert_eq!(e.text(), "G");
+        e.history_next();
+        e.history_next();
+        assert_eq!((e.text(), e.cursor()), ("F", 1));
+        e.history_next();
+        assert_eq!(e.text(), "F");
+    }
+
+    #[test]
+    fn caps_the_history_file() {
+        let path = std::env::temp_dir().join(format!("uke-tui-history-{}", std::process::id()));
+        let lines: Vec<String> = (0..HISTORY_LIMIT + 20).map(|n| n.to_string()).collect();
+        fs::write(&path, lines.join("\n")).unwrap();
+
+        let mut e = LineEditor::with_history_file(Some(path.clone()));
+        assert_eq!(e.history.len(), HISTORY_LIMIT);
+        assert_eq!(e.history[0], "20");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap().lines().count(),
+            HISTORY_LIMIT
+        );
+
+        e.push_history("C");
+        let saved = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(saved.lines().count(), HISTORY_LIMIT);
+        assert_eq!(saved.lines().next(), Some("21"));
+        assert_eq!(saved.lines().last(), Some("C"));
+    }
+}
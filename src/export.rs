@@ -0,0 +1,300 @@
+use std::{fs, io, path::Path};
+
+use uke_tui::chordpro::{self, Song};
+use uke_tui::chords::{self, Chord};
+use uke_tui::midi::Sequence;
+use uke_tui::theme::Theme;
+use uke_tui::widgets::ChordDiagram;
+use uke_tui::{html, svg};
+
+use crate::config::Config;
+use crate::grid::{Paging, combine_diagrams_grid};
+use crate::library::Library;
+
+/// Widest an exported progression gets before wrapping, in SVG pixels: about
+/// the printable width of an A4 or Letter page.
+const PAGE_WIDTH: f32 = 720.0;
+
+/// Columns and lines of a plain-text page: 10 characters per inch and 6
+/// lines per inch fill A4 and Letter alike with a margin to spare.
+const TEXT_PAGE_WIDTH: usize = 78;
+const TEXT_PAGE_HEIGHT: usize = 60;
+
+/// File formats chords can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Svg,
+    /// A printable chord sheet, with lyrics for a song
+    Html,
+    /// Fenced diagrams and a table of frets and notes, for wikis and READMEs
+    Markdown,
+    /// Framed diagrams on fixed-size pages, for printing
+    Text,
+    /// The chords played in order, one note per sounding string, for a DAW
+    Midi,
+}
+
+/// Names accepted by `--format` and `:export`.
+pub const FORMAT_NAMES: [&str; 5] = ["svg", "html", "markdown", "text", "midi"];
+
+/// What gets exported: a song (or just a title for a typed progression) and
+/// the chords to draw, each under the name it was written as.
+#[derive(Debug, Clone, Default)]
+pub struct Sheet {
+    pub song: Song,
+    pub chords: Vec<(String, Chord)>,
+}
+
+impl Format {
+    pub fn named(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "svg" => Some(Format::Svg),
+            "html" => Some(Format::Html),
+            "markdown" | "md" => Some(Format::Markdown),
+            "text" | "txt" => Some(Format::Text),
+            "midi" | "mid" => Some(Format::Midi),
+            _ => None,
+        }
+    }
+
+    /// Where `:export` writes without a file name: never `chords.txt`, the
+    /// project's library layer.
+    pub fn default_file(self) -> String {
+        format!("chords-sheet.{}", self.extension())
+    }
+
+    /// File name extension, for default output names.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Svg => "svg",
+            Format::Html => "html",
+            Format::Markdown => "md",
+            Format::Text => "txt",
+            Format::Midi => "mid",
+        }
+    }
+}
+
+/// Look up each comma-separated entry of `progression`, as typed into the
+/// input box, keeping the name as written for titles and spelling.
+pub fn progression(progression: &str, library: &Library) -> Result<Sheet, String> {
+    let mut sheet = Sheet {
+        song: Song::titled(progression.trim()),
+        chords: Vec::new(),
+    };
+    for entry in progression.split(',') {
+        let key = entry.trim();
+        if key.is_empty() {
+            continue;
+        }
+        match library.chords.iter().find(|c| c.matches_name(key)) {
+            Some(chord) => sheet.chords.push((key.to_string(), chord.clone())),
+            None => return Err(format!("Chord not found: {}", key)),
+        }
+    }
+    if sheet.chords.is_empty() {
+        return Err("no chords to export".into());
+    }
+    Ok(sheet)
+}
+
+/// A ChordPro song and the chords it uses. Slash chords fall back to their
+/// upper chord (G/B draws G); chords the library doesn't have are returned
+/// by name, to warn about, and left out of the diagrams.
+pub fn song(text: &str, library: &Library) -> (Sheet, Vec<String>) {
+    let song = chordpro::parse(text);
+    let mut chords = Vec::new();
+    let mut missing = Vec::new();
+    for name in song.chords() {
+        let upper = name.split('/').next().unwrap_or(name);
+        match library
+            .chords
+            .iter()
+            .find(|c| c.matches_name(name))
+            .or_else(|| library.chords.iter().find(|c| c.matches_name(upper)))
+        {
+            Some(chord) => chords.push((name.to_string(), chord.clone())),
+            None => missing.push(name.to_string()),
+        }
+    }
+    (Sheet { song, chords }, missing)
+}
+
+/// `chord`'s diagram over `window`, laid out and spelled as configured and
+/// titled with `name`, the way the user wrote the chord.
+pub fn diagram<'a>(
+    name: &str,
+    chord: &'a Chord,
+    (start, end): (u8, u8),
+    config: &Config,
+) -> ChordDiagram<'a> {
+    ChordDiagram::new(chord)
+        .title(name)
+        .spelled_as(chord.spelled_name(name.split(' ').next().unwrap_or(name)))
+        .window(start, end)
+        .tuning(config.tuning)
+        .accidentals(config.accidentals)
+        .view(config.view())
+}
+
+/// Render a sheet in `format`, every chord over the fret window the grid
+/// would show them in and with the configured layout and labels. MIDI plays
+/// the chords in the configured tuning, arranged as in `[midi]`, and fails
+/// if that arrangement can't be written.
+pub fn render(format: Format, sheet: &Sheet, config: &Config) -> io::Result<Vec<u8>> {
+    let window = chords::fret_window(sheet.chords.iter().map(|(_, c)| c), config.fret_window);
+    let diagrams: Vec<ChordDiagram> = sheet
+        .chords
+        .iter()
+        .map(|(name, chord)| diagram(name, chord, window, config))
+        .collect();
+    Ok(match (format, diagrams.as_slice()) {
+        (Format::Svg, [one]) => svg::chord(one).into_bytes(),
+        (Format::Svg, all) => svg::grid(all, PAGE_WIDTH).into_bytes(),
+        (Format::Html, all) => html::sheet(&sheet.song, all).into_bytes(),
+        (Format::Markdown, all) => markdown(sheet, all, config).into_bytes(),
+        (Format::Text, all) => text(sheet, all, config).into_bytes(),
+        (Format::Midi, _) => {
+            let chords = sheet.chords.iter().map(|(name, c)| (name.as_str(), c));
+            Sequence::arrange(chords, config.tuning, &config.midi)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+                .to_bytes()
+        }
+    })
+}
+
+/// The title, then a fenced block for each diagram, then a table of every
+/// chord's frets and notes (G C E A, X for a muted string).
+fn markdown(sheet: &Sheet, diagrams: &[ChordDiagram], config: &Config) -> String {
+    let mut out = format!("# {}\n\n", sheet.song.title.as_deref().unwrap_or("Chords"));
+    let meta: Vec<String> = [
+        sheet.song.key.as_ref().map(|key| format!("Key: {}", key)),
+        sheet.song.capo.map(|capo| format!("Capo {}", capo)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !meta.is_empty() {
+        out.push_str(&format!("{}\n\n", meta.join(" · ")));
+    }
+    for diagram in diagrams {
+        let text = diagram.diagram().text();
+        let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+        out.push_str(&format!("```text\n{}\n```\n\n", lines.join("\n")));
+    }
+    out.push_str("| Chord | Frets (G C E A) | Notes |\n| --- | --- | --- |\n");
+    for (name, chord) in &sheet.chords {
+        let frets: Vec<String> = chord
+            .frets
+            .iter()
+            .map(|f| f.map_or("X".to_string(), |n| n.to_string()))
+            .collect();
+        let spelled = chord.spelled_name(name);
+        let notes: Vec<String> = chord
+            .spelling(spelled, config.tuning, config.accidentals)
+            .into_iter()
+            .map(|note| note.map_or("X".to_string(), |(note, _)| note))
+            .collect();
+        out.push_str(&format!(
+            "| {} | {} | {} |\n",
+            name.replace('|', "\\|"),
+            frets.join(" "),
+            notes.join(" ")
+        ));
+    }
+    out
+}
+
+/// The diagrams framed and wrapped as in the grid, on pages
+/// `TEXT_PAGE_WIDTH` columns by `TEXT_PAGE_HEIGHT` lines headed with the
+/// title and page number.
+fn text(sheet: &Sheet, diagrams: &[ChordDiagram], config: &Config) -> String {
+    let diagrams: Vec<_> = diagrams.iter().map(ChordDiagram::diagram).collect();
+    let paging = Paging {
+        height: TEXT_PAGE_HEIGHT,
+        title: sheet.song.title.as_deref().unwrap_or("Chords"),
+    };
+    let (lines, _) = combine_diagrams_grid(
+        &diagrams,
+        None,
+        TEXT_PAGE_WIDTH,
+        config.grid_spacing,
+        &Theme::mono(),
+        Some(&paging),
+    );
+    let mut out = String::new();
+    for line in lines {
+        let text: String = line
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect();
+        out.push_str(text.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Render and write to `path`.
+pub fn write(format: Format, sheet: &Sheet, config: &Config, path: &Path) -> io::Result<()> {
+    fs::write(path, render(format, sheet, config)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sheet(chords: &[(&str, &str)]) -> Sheet {
+        Sheet {
+            song: Song {
+                key: Some("C".into()),
+                capo: Some(2),
+                ..Song::titled("Tune")
+            },
+            chords: chords
+                .iter()
+                .map(|&(name, frets)| {
+                    let chord = Chord::from_string(name, frets).expect("valid chord");
+                    (name.to_string(), chord)
+                })
+                .collect(),
+        }
+    }
+
+    fn rendered(format: Format, sheet: &Sheet) -> String {
+        let bytes = render(format, sheet, &Config::default()).expect("renders");
+        String::from_utf8(bytes).expect("utf-8")
+    }
+
+    #[test]
+    fn writes_markdown_diagrams_and_a_table() {
+        let sheet = sheet(&[("C", "0 0 0 3"), ("Bb", "3 2 1 1"), ("Am", "2 0 0 X")]);
+        let md = rendered(Format::Markdown, &sheet);
+        assert!(md.starts_with("# Tune\n\nKey: C · Capo 2\n\n```text\n"));
+        assert_eq!(md.matches("```text\n").count(), 3);
+        assert!(md.contains(
+            "| Chord | Frets (G C E A) | Notes |\n| --- | --- | --- |\n\
+             | C | 0 0 0 3 | G C E C |\n\
+             | Bb | 3 2 1 1 | Bb D F Bb |\n\
+             | Am | 2 0 0 X | A C E X |\n"
+        ));
+    }
+
+    #[test]
+    fn pages_text_to_a_fixed_size() {
+        let many: Vec<(&str, &str)> = (0..40).map(|_| ("C", "0 0 0 3")).collect();
+        let text = rendered(Format::Text, &sheet(&many));
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len() % TEXT_PAGE_HEIGHT, 0);
+        let pages = lines.len() / TEXT_PAGE_HEIGHT;
+        assert!(pages > 1);
+        for (n, page) in lines.chunks(TEXT_PAGE_HEIGHT).enumerate() {
+            assert!(page[0].starts_with("Tune"), "{}", page[0]);
+            assert!(page[0].ends_with(&format!("Page {} of {}", n + 1, pages)));
+            assert!(
+                page.iter()
+                    .all(|line| line.chars().count() <= TEXT_PAGE_WIDTH)
+            );
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use ratatui::text::{Line, Span};
+use unicode_width::UnicodeWidthStr;
+
+use uke_tui::theme::Theme;
+use uke_tui::widgets::Diagram;
+
+/// Where a panel landed in the last grid layout.
+#[derive(Clone, Copy)]
+pub struct GridCell {
+    pub row: usize,
+    pub x: usize,
+    pub top: u16,
+    pub height: u16,
+}
+
+/// Lines at the top of every page: the header and a blank line.
+const HEADER_LINES: usize = 2;
+
+/// Fixed-size pages for printing, each `height` lines long and headed with
+/// `title` and the page number.
+pub struct Paging<'a> {
+    pub height: usize,
+    pub title: &'a str,
+}
+
+/// Arrange diagrams into rows that wrap at `max_width`, spacing them by `spacing` columns,
+/// framing each block and padding each line to the display‐width of its block.
+/// The `focus` block gets a heavy frame in the theme's accent. Also returns where each block landed.
+///
+/// With `paging`, the lines are cut into pages that each start with a header: a row that
+/// won't fit on the rest of a page moves to the next, so no diagram is split across a
+/// page break (unless it is taller than a page), and the last page is padded to full height.
+pub fn combine_diagrams_grid(
+    diagrams: &[Diagram],
+    focus: Option<usize>,
+    max_width: usize,
+    spacing: usize,
+    theme: &Theme,
+    paging: Option<&Paging>,
+) -> (Vec<Line<'static>>, Vec<GridCell>) {
+    let blocks: Vec<Vec<Line<'static>>> = diagrams.iter().map(|d| d.styled(theme)).collect();
+
+    // Content width plus the two frame columns
+    let widths: Vec<usize> = diagrams.iter().map(|d| d.width() + 2).collect();
+
+    let heights: Vec<usize> = blocks.iter().map(|lines| lines.len() + 2).collect();
+
+    // Pack block indices into rows
+    let mut rows: Vec<Vec<usize>> = Vec::new();
+    let mut cur: Vec<usize> = Vec::new();
+    let mut used_w = 0;
+
+    for (i, &w) in widths.iter().enumerate() {
+        let needed = if cur.is_empty() {
+            w
+        } else {
+            used_w + spacing + w
+        };
+        if needed > max_width && !cur.is_empty() {
+            rows.push(cur);
+            cur = vec![i];
+            used_w = w;
+        } else {
+            if cur.is_empty() {
+                used_w = w;
+            } else {
+                used_w = used_w + spacing + w;
+            }
+            cur.push(i);
+        }
+    }
+    if !cur.is_empty() {
+        rows.push(cur);
+    }
+
+    // Build each output line
+    let mut out: Vec<Line<'static>> = Vec::new();
+    let mut cells = vec![
+        GridCell {
+            row: 0,
+            x: 0,
+            top: 0,
+            height: 0,
+        };
+        blocks.len()
+    ];
+
+    // Where each page's header goes, filled in once the page count is known
+    let mut headers: Vec<usize> = Vec::new();
+
+    for (row_i, row) in rows.iter().enumerate() {
+        // How tall is this row?
+        let row_h = row.iter().map(|&i| heights[i]).max().unwrap_or(0);
+
+        if let Some(paging) = paging {
+            let used = out.len() % paging.height;
+            if used > HEADER_LINES && used + row_h > paging.height {
+                out.resize(out.len() + paging.height - used, Line::default());
+            }
+            if out.len().is_multiple_of(paging.height) {
+                headers.push(out.len());
+                out.resize(out.len() + HEADER_LINES, Line::default());
+            }
+        }
+
+        let mut x = 0;
+        for &block_i in row {
+            cells[block_i] = GridCell {
+                row: row_i,
+                x,
+                top: out.len() as u16,
+                height: heights[block_i] as u16,
+            };
+            x += widths[block_i] + spacing;
+        }
+
+        for line_idx in 0..row_h {
+            let mut spans: Vec<Span<'static>> = Vec::new();
+
+            for (j, &block_i) in row.iter().enumerate() {
+                let focused = focus == Some(block_i);
+                let (frame, style) = if focused {
+                    (HEAVY_FRAME, theme.accent)
+                } else {
+                    (LIGHT_FRAME, theme.frame)
+                };
+                let inner = widths[block_i] - 2;
+                let last = heights[block_i] - 1;
+
+                if line_idx == 0 || line_idx == last {
+                    let (l, r) = if line_idx == 0 {
+                        (frame[0], frame[2])
+                    } else {
+                        (frame[5], frame[6])
+                    };
+                    let edge = format!("{}{}{}", l, frame[1].repeat(inner), r);
+                    spans.push(Span::styled(edge, style));
+                } else if line_idx < last {
+                    // Get the text for this line, or "" if the block has fewer lines
+                    let cell = blocks[block_i].get(line_idx - 1);
+                    let pad = inner.saturating_sub(cell.map_or(0, Line::width));
+                    spans.push(Span::styled(frame[3], style));
+                    spans.extend(cell.into_iter().flat_map(|l| l.spans.iter().cloned()));
+                    // Pad with spaces to the block’s full width
+                    spans.push(Span::raw(" ".repeat(pad)));
+                    spans.push(Span::styled(frame[4], style));
+                } else {
+                    spans.push(Span::raw(" ".repeat(widths[block_i])));
+                }
+
+                // Spacing between blocks
+                if j + 1 < row.len() {
+                    spans.push(Span::raw(" ".repeat(spacing)));
+                }
+            }
+
+            // A row taller than a page spills onto the next under its own header
+            if let Some(paging) = paging
+                && out.len().is_multiple_of(paging.height)
+            {
+                headers.push(out.len());
+                out.resize(out.len() + HEADER_LINES, Line::default());
+            }
+            out.push(Line::from(spans));
+        }
+    }
+
+    if let Some(paging) = paging {
+        let pages = headers.len();
+        for (n, &at) in headers.iter().enumerate() {
+            let number = format!("Page {} of {}", n + 1, pages);
+            // Shorten a long title so the page number stays on the page
+            let room = max_width.saturating_sub(number.width() + 2);
+            let mut title = paging.title.to_string();
+            if title.width() > room {
+                while title.width() + 1 > room && title.pop().is_some() {}
+                title.push('…');
+            }
+            let gap = max_width
+                .saturating_sub(title.width() + number.width())
+                .max(2);
+            out[at] = Line::styled(
+                format!("{}{}{}", title, " ".repeat(gap), number),
+                theme.title,
+            );
+        }
+        let used = out.len() % paging.height;
+        if used > 0 {
+            out.resize(out.len() + paging.height - used, Line::default());
+        }
+    }
+
+    (out, cells)
+}
+
+/// Frame pieces: top-left, horizontal, top-right, left, right, bottom-left, bottom-right.
+const LIGHT_FRAME: [&str; 7] = ["┌", "─", "┐", "│", "│", "└", "┘"];
+const HEAVY_FRAME: [&str; 7] = ["┏", "━", "┓", "┃", "┃", "┗", "┛"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uke_tui::chords::Chord;
+    use uke_tui::widgets::ChordDiagram;
+
+    fn diagrams(count: usize) -> Vec<Diagram> {
+        let c = Chord::from_string("C", "0 0 0 3").expect("valid chord");
+        (0..count)
+            .map(|_| ChordDiagram::new(&c).title("C").diagram())
+            .collect()
+    }
+
+    /// The text of each page's first line.
+    fn headers(lines: &[Line], height: usize) -> Vec<String> {
+        lines
+            .chunks(height)
+            .map(|page| page[0].spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn pages_rows_without_splitting_them() {
+        let diagrams = diagrams(6);
+        let row_h = diagrams[0].styled(&Theme::mono()).len() + 2;
+        // Room for two rows a page, one diagram a row
+        let height = HEADER_LINES + 2 * row_h + 1;
+        let paging = Paging {
+            height,
+            title: "Song",
+        };
+        let width = diagrams[0].width() + 2;
+        let (lines, _) = combine_diagrams_grid(
+            &diagrams,
+            None,
+            width + 20,
+            2,
+            &Theme::mono(),
+            Some(&paging),
+        );
+        assert_eq!(lines.len(), 3 * height);
+        for (n, header) in headers(&lines, height).iter().enumerate() {
+            assert!(header.starts_with("Song"), "{}", header);
+            assert!(
+                header.ends_with(&format!("Page {} of 3", n + 1)),
+                "{}",
+                header
+            );
+        }
+        // Every page's rows start right under the header
+        for page in lines.chunks(height) {
+            let top: String = page[HEADER_LINES]
+                .spans
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect();
+            assert!(top.starts_with('┌'), "{}", top);
+        }
+    }
+
+    #[test]
+    fn heads_the_pages_a_tall_row_spills_onto() {
+        let diagrams = diagrams(1);
+        let row_h = diagrams[0].styled(&Theme::mono()).len() + 2;
+        let height = HEADER_LINES + row_h / 2;
+        let paging = Paging {
+            height,
+            title: "Tall",
+        };
+        let (lines, _) =
+            combine_diagrams_grid(&diagrams, None, 78, 2, &Theme::mono(), Some(&paging));
+        let pages = lines.len() / height;
+        assert_eq!(lines.len() % height, 0);
+        assert_eq!(pages, row_h.div_ceil(height - HEADER_LINES));
+        for (n, header) in headers(&lines, height).iter().enumerate() {
+            assert!(header.starts_with("Tall"), "{}", header);
+            assert!(
+                header.ends_with(&format!("Page {} of {}", n + 1, pages)),
+                "{}",
+                header
+            );
+        }
+    }
+}
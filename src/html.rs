@@ -0,0 +1,153 @@
+use std::fmt::Write;
+
+use crate::chordpro::{Song, SongLine};
+use crate::svg::{self, escape};
+use crate::widgets::ChordDiagram;
+
+/// Styles for the sheet. The page is kept 180mm wide, which fits inside the
+/// margins of both A4 and US Letter, so the paper size is left to the
+/// printer; diagrams and lyric lines are never split across pages.
+const STYLE: &str = "\
+@page { margin: 15mm; }
+body { font-family: sans-serif; color: #000; background: #fff; max-width: 180mm; margin: 2em auto; }
+@media print { body { margin: 0; } }
+header { border-bottom: 1px solid #000; margin-bottom: 4mm; }
+h1 { font-size: 20pt; margin: 0; }
+.subtitle { font-size: 12pt; margin: 1mm 0 0; }
+.meta { font-size: 11pt; margin: 2mm 0; }
+.meta span + span::before { content: \" · \"; }
+.diagrams { display: flex; flex-wrap: wrap; gap: 2mm 4mm; margin-bottom: 6mm; }
+.diagrams svg { break-inside: avoid; }
+.lyrics { font-size: 12pt; line-height: 1.2; }
+.line { display: flex; flex-wrap: wrap; align-items: flex-end; break-inside: avoid; }
+.chunk { display: inline-flex; flex-direction: column; white-space: pre-wrap; }
+.chord { font-weight: bold; padding-right: 0.4em; }
+.chord, .words { min-height: 1.2em; }
+.blank { height: 1em; }
+.comment { font-style: italic; color: #444; margin: 2mm 0; }
+.section { margin: 2mm 0; }
+.chorus { border-left: 2px solid #000; padding-left: 4mm; }
+";
+
+/// A printable chord sheet as one self-contained HTML file, styles and
+/// diagrams inline: the song's title, key and capo, a strip with a diagram
+/// for each chord, then the lyrics with every chord over the word it falls
+/// on. A song without lyrics (a typed progression) is just the strip.
+pub fn sheet(song: &Song, diagrams: &[ChordDiagram]) -> String {
+    let title = song.title.as_deref().unwrap_or("Chords");
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>",
+        escape(title),
+        STYLE
+    );
+
+    out.push_str("<header>\n");
+    let _ = writeln!(out, "<h1>{}</h1>", escape(title));
+    if let Some(subtitle) = song.subtitle.as_ref().or(song.artist.as_ref()) {
+        let _ = writeln!(out, "<p class=\"subtitle\">{}</p>", escape(subtitle));
+    }
+    let mut meta = Vec::new();
+    if let Some(key) = &song.key {
+        meta.push(format!("Key: {}", key));
+    }
+    if let Some(capo) = song.capo {
+        meta.push(format!("Capo {}", capo));
+    }
+    if !meta.is_empty() {
+        out.push_str("<p class=\"meta\">");
+        for item in meta {
+            let _ = write!(out, "<span>{}</span>", escape(&item));
+        }
+        out.push_str("</p>\n");
+    }
+    out.push_str("</header>\n");
+
+    out.push_str("<section class=\"diagrams\">\n");
+    for diagram in diagrams {
+        out.push_str(&svg::chord(diagram));
+    }
+    out.push_str("</section>\n");
+
+    if !song.lines.is_empty() {
+        out.push_str("<section class=\"lyrics\">\n");
+        let mut open = 0;
+        for line in &song.lines {
+            match line {
+                SongLine::Lyrics(segments) => lyric_line(&mut out, segments),
+                SongLine::Comment(text) => {
+                    let _ = writeln!(out, "<p class=\"comment\">{}</p>", escape(text));
+                }
+                SongLine::Start(kind) => {
+                    let _ = writeln!(out, "<div class=\"section {}\">", escape(kind));
+                    open += 1;
+                }
+                SongLine::End if open > 0 => {
+                    out.push_str("</div>\n");
+                    open -= 1;
+                }
+                SongLine::End => {}
+                SongLine::Blank => out.push_str("<div class=\"blank\"></div>\n"),
+            }
+        }
+        out.push_str(&"</div>\n".repeat(open));
+        out.push_str("</section>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// One line of lyrics, each chord stacked over the words sung from it. Lines
+/// without chords leave out the chord row.
+fn lyric_line(out: &mut String, segments: &[(String, String)]) {
+    let chorded = segments.iter().any(|(chord, _)| !chord.is_empty());
+    out.push_str("<div class=\"line\">");
+    for (chord, words) in segments {
+        out.push_str("<span class=\"chunk\">");
+        if chorded {
+            let _ = write!(out, "<span class=\"chord\">{}</span>", escape(chord));
+        }
+        let _ = write!(out, "<span class=\"words\">{}</span></span>", escape(words));
+    }
+    out.push_str("</div>\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chordpro;
+    use crate::chords::Chord;
+
+    #[test]
+    fn keeps_the_song_around_the_diagrams() {
+        let song = chordpro::parse(
+            "{title: Rose & Thorn}\n{key: C}\n{capo: 3}\n{soc}\n[C]Roses <red>\n{eoc}\nplain words\n",
+        );
+        let c = Chord::from_string("C", "0 0 0 3").expect("valid chord");
+        let html = sheet(&song, &[ChordDiagram::new(&c).title("C")]);
+
+        assert!(html.contains("<title>Rose &amp; Thorn</title>"));
+        assert!(html.contains("<span>Key: C</span><span>Capo 3</span>"));
+        assert_eq!(html.matches("<svg").count(), 1);
+        assert!(html.contains(
+            "<div class=\"section chorus\">\n<div class=\"line\"><span class=\"chunk\">\
+             <span class=\"chord\">C</span><span class=\"words\">Roses &lt;red&gt;</span></span></div>\n</div>"
+        ));
+        // A line without chords has no chord row
+        assert!(html.contains(
+            "<div class=\"line\"><span class=\"chunk\"><span class=\"words\">plain words</span></span></div>"
+        ));
+        // Diagrams come before the lyrics
+        assert!(html.find("class=\"diagrams\"") < html.find("class=\"lyrics\""));
+    }
+
+    #[test]
+    fn a_progression_is_just_the_strip() {
+        let html = sheet(&chordpro::Song::titled("C, G"), &[]);
+        assert!(html.contains("<h1>C, G</h1>"));
+        assert!(!html.contains("class=\"lyrics\""));
+        assert!(!html.contains("class=\"meta\""));
+    }
+}
@@ -0,0 +1,622 @@
+use std::{collections::BTreeMap, fmt};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// Where a key press is handled. Global bindings apply on top of the mode
+/// being used; line editing applies to the input box and the save prompt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Global,
+    Input,
+    Edit,
+    Prompt,
+    Grid,
+    Builder,
+    Help,
+}
+
+/// Help heading for each mode, in the order the help lists them.
+const MODES: [(Mode, &str); 7] = [
+    (Mode::Global, "Anywhere"),
+    (Mode::Input, "Input box"),
+    (Mode::Edit, "Line editing (input box and the save prompt)"),
+    (Mode::Prompt, "Help search and the save prompt"),
+    (Mode::Grid, "Diagram grid"),
+    (Mode::Builder, "Chord builder (:build)"),
+    (Mode::Help, "This help"),
+];
+
+/// Columns for the keys in the help; longer lists push their line along.
+const HELP_KEY_WIDTH: usize = 14;
+
+/// Everything a key can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Help,
+    Quit,
+    AcceptCompletion,
+    Lookup,
+    PickSuggestion,
+    NextCompletion,
+    PrevCompletion,
+    Select,
+    HistoryPrev,
+    HistoryNext,
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    ScrollTop,
+    ScrollBottom,
+    CloseCompletions,
+    Cancel,
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    LineStart,
+    LineEnd,
+    DeleteChar,
+    Backspace,
+    KillToStart,
+    KillToEnd,
+    KillWord,
+    Confirm,
+    Dismiss,
+    CloseDetails,
+    LeaveGrid,
+    FocusLeft,
+    FocusRight,
+    FocusUp,
+    FocusDown,
+    Voicing,
+    PrevVoicing,
+    Details,
+    Remove,
+    MoveLeft,
+    MoveRight,
+    Copy,
+    Edit,
+    Turn,
+    Labels,
+    FretLeft,
+    FretRight,
+    StringUp,
+    StringDown,
+    ToNut,
+    ToLastFret,
+    Toggle,
+    Mute,
+    SlideUp,
+    SlideDown,
+    Undo,
+    Redo,
+    Clear,
+    Save,
+    Show,
+    Close,
+    CloseHelp,
+    HelpUp,
+    HelpDown,
+    HelpPageUp,
+    HelpPageDown,
+    HelpSearch,
+    HelpNextMatch,
+    HelpPrevMatch,
+}
+
+/// One bindable action: its mode, config name, default keys, help text and
+/// footer hint ("" for none; actions sharing a hint are shown together).
+/// Within a mode, earlier actions get first go at a key they share.
+type Entry = (
+    Action,
+    Mode,
+    &'static str,
+    &'static [&'static str],
+    &'static str,
+    &'static str,
+);
+
+#[rustfmt::skip]
+const ACTIONS: &[Entry] = &[
+    (Action::Help, Mode::Global, "help", &["?"], "show or hide this help", "help"),
+    (Action::Quit, Mode::Global, "quit", &["C-c"], "quit", "quit"),
+
+    (Action::AcceptCompletion, Mode::Input, "accept-completion", &["Enter"], "use the highlighted completion", ""),
+    (Action::Lookup, Mode::Input, "lookup", &["Enter"], "look up the chords", "lookup"),
+    (Action::PickSuggestion, Mode::Input, "pick-suggestion", &["1", "2", "3", "4", "5", "6", "7", "8", "9"], "use the first, second… \"did you mean\" suggestion (empty input)", ""),
+    (Action::NextCompletion, Mode::Input, "next-completion", &["Tab", "Down"], "next completion, while they're shown", "complete"),
+    (Action::PrevCompletion, Mode::Input, "previous-completion", &["S-Tab", "Up"], "previous completion", ""),
+    (Action::Select, Mode::Input, "select", &["Tab"], "select chords in the diagram grid", "select"),
+    (Action::HistoryPrev, Mode::Input, "history-previous", &["C-p"], "previous input from history", ""),
+    (Action::HistoryNext, Mode::Input, "history-next", &["C-n"], "next input from history", ""),
+    (Action::ScrollUp, Mode::Input, "scroll-up", &["Up"], "scroll the diagrams up (also the mouse wheel)", "scroll"),
+    (Action::ScrollDown, Mode::Input, "scroll-down", &["Down"], "scroll the diagrams down", "scroll"),
+    (Action::PageUp, Mode::Input, "page-up", &["PageUp"], "scroll up a page", ""),
+    (Action::PageDown, Mode::Input, "page-down", &["PageDown"], "scroll down a page", ""),
+    (Action::ScrollTop, Mode::Input, "scroll-top", &["C-Home", "Home"], "first row (unmodified only when the input is empty)", ""),
+    (Action::ScrollBottom, Mode::Input, "scroll-bottom", &["C-End", "End"], "last row (unmodified only when the input is empty)", ""),
+    (Action::CloseCompletions, Mode::Input, "close-completions", &["Esc"], "close the completions", ""),
+    (Action::Cancel, Mode::Input, "cancel", &["Esc"], "quit", "quit"),
+
+    (Action::Left, Mode::Edit, "left", &["Left", "C-b"], "move the cursor left", ""),
+    (Action::Right, Mode::Edit, "right", &["Right", "C-f"], "move the cursor right", ""),
+    (Action::WordLeft, Mode::Edit, "word-left", &["C-Left", "M-Left", "M-b"], "move back a word", ""),
+    (Action::WordRight, Mode::Edit, "word-right", &["C-Right", "M-Right", "M-f"], "move forward a word", ""),
+    (Action::LineStart, Mode::Edit, "line-start", &["Home", "C-a"], "move to the start", ""),
+    (Action::LineEnd, Mode::Edit, "line-end", &["End", "C-e"], "move to the end", ""),
+    (Action::DeleteChar, Mode::Edit, "delete", &["Delete", "C-d"], "delete the character under the cursor", ""),
+    (Action::Backspace, Mode::Edit, "backspace", &["Backspace"], "delete the character before the cursor", ""),
+    (Action::KillToStart, Mode::Edit, "kill-to-start", &["C-u"], "delete to the start", ""),
+    (Action::KillToEnd, Mode::Edit, "kill-to-end", &["C-k"], "delete to the end", ""),
+    (Action::KillWord, Mode::Edit, "kill-word", &["C-w", "C-Backspace", "M-Backspace"], "delete the word before the cursor", ""),
+
+    (Action::Confirm, Mode::Prompt, "confirm", &["Enter"], "finish the search, or save under the name typed", "save"),
+    (Action::Dismiss, Mode::Prompt, "dismiss", &["Esc"], "cancel the search or the save", "cancel"),
+
+    (Action::CloseDetails, Mode::Grid, "close-details", &["Esc"], "hide the details pane", ""),
+    (Action::LeaveGrid, Mode::Grid, "leave", &["Tab", "Esc", "/"], "back to the input", "input"),
+    (Action::FocusLeft, Mode::Grid, "focus-left", &["Left", "h"], "previous chord", "move"),
+    (Action::FocusRight, Mode::Grid, "focus-right", &["Right", "l"], "next chord", "move"),
+    (Action::FocusUp, Mode::Grid, "focus-up", &["Up", "k"], "chord in the row above", "move"),
+    (Action::FocusDown, Mode::Grid, "focus-down", &["Down", "j"], "chord in the row below", "move"),
+    (Action::Voicing, Mode::Grid, "voicing", &["v"], "next voicing", "voicing"),
+    (Action::PrevVoicing, Mode::Grid, "previous-voicing", &["V"], "previous voicing", ""),
+    (Action::Details, Mode::Grid, "details", &["i", "Enter"], "show or hide the details pane", "details"),
+    (Action::Remove, Mode::Grid, "remove", &["x", "Delete"], "remove from the progression", "remove"),
+    (Action::MoveLeft, Mode::Grid, "move-left", &["H", "<"], "move earlier in the progression", "reorder"),
+    (Action::MoveRight, Mode::Grid, "move-right", &["L", ">"], "move later in the progression", "reorder"),
+    (Action::Copy, Mode::Grid, "copy", &["y"], "copy the diagram to the clipboard", "copy"),
+    (Action::Edit, Mode::Grid, "edit", &["e"], "edit the shape in the chord builder", "edit"),
+    (Action::Turn, Mode::Grid, "turn", &["o"], "switch between horizontal and vertical diagrams", ""),
+    (Action::Labels, Mode::Grid, "labels", &["t"], "label the dots with notes, intervals or fingers", ""),
+
+    (Action::FretLeft, Mode::Builder, "fret-left", &["Left", "h"], "move the cursor towards the nut", "move"),
+    (Action::FretRight, Mode::Builder, "fret-right", &["Right", "l"], "move the cursor up the neck", "move"),
+    (Action::StringUp, Mode::Builder, "string-up", &["Up", "k"], "move the cursor up a string", "move"),
+    (Action::StringDown, Mode::Builder, "string-down", &["Down", "j"], "move the cursor down a string", "move"),
+    (Action::ToNut, Mode::Builder, "to-nut", &["Home"], "jump to the nut", ""),
+    (Action::ToLastFret, Mode::Builder, "to-last-fret", &["End"], "jump to the last fret", ""),
+    (Action::Toggle, Mode::Builder, "toggle", &["Space"], "place or lift a finger (nut: open / mute; or click)", "finger"),
+    (Action::Mute, Mode::Builder, "mute", &["x"], "mute the string under the cursor", "mute"),
+    (Action::SlideUp, Mode::Builder, "slide-up", &["+", "="], "slide that string's finger up the neck", "slide"),
+    (Action::SlideDown, Mode::Builder, "slide-down", &["-"], "slide that string's finger down the neck", "slide"),
+    (Action::Undo, Mode::Builder, "undo", &["u", "C-z"], "undo", "undo"),
+    (Action::Redo, Mode::Builder, "redo", &["U", "C-r", "C-y"], "redo", "redo"),
+    (Action::Clear, Mode::Builder, "clear", &["c"], "clear all strings", ""),
+    (Action::Save, Mode::Builder, "save", &["s"], "save as: replaces the entry of that name in the library, or adds one", "save"),
+    (Action::Show, Mode::Builder, "show", &["Enter"], "show the shape in the diagram grid", ""),
+    (Action::Close, Mode::Builder, "close", &["Esc"], "close the builder", "close"),
+
+    (Action::CloseHelp, Mode::Help, "close-help", &["Esc"], "close the help", ""),
+    (Action::HelpUp, Mode::Help, "help-up", &["Up"], "scroll up (also the mouse wheel)", ""),
+    (Action::HelpDown, Mode::Help, "help-down", &["Down"], "scroll down", ""),
+    (Action::HelpPageUp, Mode::Help, "help-page-up", &["PageUp"], "scroll up a page", ""),
+    (Action::HelpPageDown, Mode::Help, "help-page-down", &["PageDown"], "scroll down a page", ""),
+    (Action::HelpSearch, Mode::Help, "search", &["/"], "search the help as you type", ""),
+    (Action::HelpNextMatch, Mode::Help, "next-match", &["n"], "next match", ""),
+    (Action::HelpPrevMatch, Mode::Help, "previous-match", &["N"], "previous match", ""),
+];
+
+/// Keys the vi preset uses instead of the defaults.
+#[rustfmt::skip]
+const VI: &[(Action, &[&str])] = &[
+    (Action::LeaveGrid, &["i", "a", "Esc", "Tab", "/"]),
+    (Action::Details, &["K", "Enter"]),
+    (Action::Remove, &["x", "d", "Delete"]),
+    (Action::ToNut, &["0", "^", "Home"]),
+    (Action::ToLastFret, &["$", "End"]),
+    (Action::Undo, &["u"]),
+    (Action::Redo, &["C-r", "U"]),
+    (Action::Clear, &["D", "c"]),
+    (Action::Close, &["Esc", "q"]),
+    (Action::CloseHelp, &["Esc", "q"]),
+    (Action::HelpUp, &["k", "Up"]),
+    (Action::HelpDown, &["j", "Down"]),
+    (Action::HelpPageUp, &["C-b", "C-u", "PageUp"]),
+    (Action::HelpPageDown, &["C-f", "C-d", "Space", "PageDown"]),
+];
+
+/// Keys the emacs preset uses instead of the defaults.
+#[rustfmt::skip]
+const EMACS: &[(Action, &[&str])] = &[
+    (Action::CloseCompletions, &["C-g", "Esc"]),
+    (Action::Dismiss, &["C-g", "Esc"]),
+    (Action::PageUp, &["M-v", "PageUp"]),
+    (Action::PageDown, &["C-v", "PageDown"]),
+    (Action::ScrollTop, &["M-<", "C-Home", "Home"]),
+    (Action::ScrollBottom, &["M->", "C-End", "End"]),
+    (Action::CloseDetails, &["C-g", "Esc"]),
+    (Action::LeaveGrid, &["C-g", "Esc", "Tab"]),
+    (Action::FocusLeft, &["C-b", "Left"]),
+    (Action::FocusRight, &["C-f", "Right"]),
+    (Action::FocusUp, &["C-p", "Up"]),
+    (Action::FocusDown, &["C-n", "Down"]),
+    (Action::Details, &["Enter"]),
+    (Action::Remove, &["C-d", "Delete"]),
+    (Action::Copy, &["M-w"]),
+    (Action::FretLeft, &["C-b", "Left"]),
+    (Action::FretRight, &["C-f", "Right"]),
+    (Action::StringUp, &["C-p", "Up"]),
+    (Action::StringDown, &["C-n", "Down"]),
+    (Action::ToNut, &["C-a", "Home"]),
+    (Action::ToLastFret, &["C-e", "End"]),
+    (Action::Undo, &["C-/", "C-z"]),
+    (Action::Redo, &["M-_", "C-M-_"]),
+    (Action::Clear, &["C-k"]),
+    (Action::Save, &["C-s"]),
+    (Action::Close, &["C-g", "Esc"]),
+    (Action::CloseHelp, &["C-g", "Esc", "q"]),
+    (Action::HelpUp, &["C-p", "Up"]),
+    (Action::HelpDown, &["C-n", "Down"]),
+    (Action::HelpPageUp, &["M-v", "PageUp"]),
+    (Action::HelpPageDown, &["C-v", "Space", "PageDown"]),
+    (Action::HelpSearch, &["C-s", "/"]),
+];
+
+/// Starting set of bindings, chosen with `keymap` in the config file.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    #[default]
+    Default,
+    Vi,
+    Emacs,
+}
+
+/// One key or several, as written in the config file.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// A key press written like "?", "Tab", "C-c", "M-f", "S-Tab" or "F1".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeySpec {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeySpec {
+    pub fn parse(s: &str) -> Option<KeySpec> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        while rest.len() > 2
+            && let Some((m, tail)) = rest.split_once('-')
+        {
+            modifiers |= match m {
+                "C" => KeyModifiers::CONTROL,
+                "M" | "A" => KeyModifiers::ALT,
+                "S" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+            rest = tail;
+        }
+        let code = match rest {
+            "Tab" if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            "Tab" => KeyCode::Tab,
+            "Enter" => KeyCode::Enter,
+            "Esc" => KeyCode::Esc,
+            "Space" => KeyCode::Char(' '),
+            "Backspace" => KeyCode::Backspace,
+            "Delete" | "Del" => KeyCode::Delete,
+            "Insert" => KeyCode::Insert,
+            "Home" => KeyCode::Home,
+            "End" => KeyCode::End,
+            "PageUp" => KeyCode::PageUp,
+            "PageDown" => KeyCode::PageDown,
+            "Up" => KeyCode::Up,
+            "Down" => KeyCode::Down,
+            "Left" => KeyCode::Left,
+            "Right" => KeyCode::Right,
+            f if f.len() > 1 && f.starts_with('F') => KeyCode::F(f[1..].parse().ok()?),
+            c => {
+                let mut chars = c.chars();
+                let ch = chars.next()?;
+                if chars.next().is_some() {
+                    return None;
+                }
+                // Terminals send C-/ and C-_ as the same control byte, 0x1F,
+                // which crossterm reads as C-7
+                match ch {
+                    '/' | '_' if modifiers.contains(KeyModifiers::CONTROL) => KeyCode::Char('7'),
+                    ch => KeyCode::Char(ch),
+                }
+            }
+        };
+        Some(KeySpec { code, modifiers })
+    }
+
+    /// Whether a key event is this key. Shift is implied by the character
+    /// itself ("?" or "H"), and by BackTab, so it is ignored for those.
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let mut modifiers = key.modifiers;
+        if let KeyCode::Char(_) | KeyCode::BackTab = key.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        key.code == self.code && modifiers == self.modifiers
+    }
+}
+
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "C-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "M-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "S-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char('7') if self.modifiers.contains(KeyModifiers::CONTROL) => write!(f, "/"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::BackTab => write!(f, "S-Tab"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// The `[keys]` table from the config file, checked: each listed action
+/// with the keys that replace its preset ones.
+#[derive(Debug, Default)]
+pub struct KeyOverrides(Vec<(Action, Vec<KeySpec>)>);
+
+impl KeyOverrides {
+    pub fn from_table(table: &BTreeMap<String, KeyList>) -> Result<Self, String> {
+        let mut overrides = Vec::new();
+        for (name, list) in table {
+            let Some(&(action, ..)) = ACTIONS.iter().find(|entry| entry.2 == name) else {
+                return Err(format!(
+                    "unknown action `{}` in [keys], see the help (?) for the list",
+                    name
+                ));
+            };
+            let keys = match list {
+                KeyList::One(k) => std::slice::from_ref(k),
+                KeyList::Many(ks) => ks.as_slice(),
+            };
+            let specs = keys
+                .iter()
+                .map(|k| {
+                    KeySpec::parse(k).ok_or_else(|| format!("invalid key `{}` for {}", k, name))
+                })
+                .collect::<Result<_, _>>()?;
+            overrides.push((action, specs));
+        }
+        Ok(KeyOverrides(overrides))
+    }
+}
+
+/// Keys bound to each action: a preset, with any action the config file
+/// lists having its keys replaced entirely.
+#[derive(Debug, Clone)]
+pub struct KeyBindings {
+    keys: Vec<(Action, KeySpec)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::new(Preset::Default, &KeyOverrides::default())
+    }
+}
+
+impl KeyBindings {
+    pub fn new(preset: Preset, overrides: &KeyOverrides) -> Self {
+        let preset_keys = match preset {
+            Preset::Default => &[][..],
+            Preset::Vi => VI,
+            Preset::Emacs => EMACS,
+        };
+        let mut keys = Vec::new();
+        for &(action, _, _, defaults, _, _) in ACTIONS {
+            if let Some((_, specs)) = overrides.0.iter().find(|(a, _)| *a == action) {
+                keys.extend(specs.iter().map(|&k| (action, k)));
+                continue;
+            }
+            let names = preset_keys
+                .iter()
+                .find(|(a, _)| *a == action)
+                .map_or(defaults, |(_, k)| *k);
+            keys.extend(
+                names
+                    .iter()
+                    .map(|k| (action, KeySpec::parse(k).expect("valid preset key"))),
+            );
+        }
+        Self { keys }
+    }
+
+    /// Actions of `mode` that `key` is bound to, in the order they should be
+    /// tried. Global bindings are not included.
+    pub fn actions<'a>(
+        &'a self,
+        mode: Mode,
+        key: &'a KeyEvent,
+    ) -> impl Iterator<Item = Action> + 'a {
+        ACTIONS
+            .iter()
+            .filter(move |entry| entry.1 == mode)
+            .map(|entry| entry.0)
+            .filter(move |&action| {
+                self.keys
+                    .iter()
+                    .any(|(a, k)| *a == action && k.matches(key))
+            })
+    }
+
+    /// The keys for an action as shown in help, e.g. "y" or "x, Del".
+    pub fn label(&self, action: Action) -> String {
+        let keys: Vec<String> = self
+            .keys
+            .iter()
+            .filter(|(a, _)| *a == action)
+            .map(|(_, k)| k.to_string())
+            .collect();
+        if keys.is_empty() {
+            "(unbound)".into()
+        } else {
+            keys.join(", ")
+        }
+    }
+
+    /// The keys bound to an action, in the order they were given.
+    pub fn bound(&self, action: Action) -> impl Iterator<Item = &KeySpec> {
+        self.keys
+            .iter()
+            .filter(move |(a, _)| *a == action)
+            .map(|(_, k)| k)
+    }
+
+    /// The first key for an action, for short hints.
+    pub fn key(&self, action: Action) -> String {
+        self.keys
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or("(unbound)".into(), |(_, k)| k.to_string())
+    }
+
+    /// The footer line for a mode: each hinted action's first key, actions
+    /// sharing a hint grouped, then the global ones.
+    pub fn hints(&self, mode: Mode) -> String {
+        let mut groups: Vec<(&str, Vec<String>)> = Vec::new();
+        let entries = ACTIONS.iter().filter(|e| e.1 == mode);
+        for &(action, _, _, _, _, hint) in
+            entries.chain(ACTIONS.iter().filter(|e| e.1 == Mode::Global))
+        {
+            if hint.is_empty() {
+                continue;
+            }
+            let Some((_, key)) = self.keys.iter().find(|(a, _)| *a == action) else {
+                continue;
+            };
+            let key = key.to_string();
+            match groups.iter_mut().find(|(h, _)| *h == hint) {
+                Some((_, keys)) if !keys.contains(&key) => keys.push(key),
+                Some(_) => {}
+                None => groups.push((hint, vec![key])),
+            }
+        }
+        groups
+            .iter()
+            .map(|(hint, keys)| format!("{}:{}", keys.join("/"), hint))
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+
+    /// Every binding, grouped by mode, for the help overlay: the keys, what
+    /// they do and the action's name for `[keys]`.
+    pub fn help(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (mode, heading) in MODES {
+            lines.push(heading.to_string());
+            for &(action, _, name, _, help, _) in ACTIONS.iter().filter(|e| e.1 == mode) {
+                let keys = self.label(action);
+                // Pad by characters: the arrow keys are several bytes each
+                let pad = HELP_KEY_WIDTH.saturating_sub(keys.chars().count());
+                lines.push(format!(
+                    "  {}{} {}  ({})",
+                    keys,
+                    " ".repeat(pad),
+                    help,
+                    name
+                ));
+            }
+            lines.push(String::new());
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn parses_modifiers_and_names() {
+        let spec = KeySpec::parse("C-M-f").expect("valid");
+        assert!(spec.matches(&press(
+            KeyCode::Char('f'),
+            KeyModifiers::CONTROL | KeyModifiers::ALT
+        )));
+        assert!(!spec.matches(&press(KeyCode::Char('f'), KeyModifiers::CONTROL)));
+        assert_eq!(
+            KeySpec::parse("PageDown").map(|k| k.code),
+            Some(KeyCode::PageDown)
+        );
+        assert_eq!(KeySpec::parse("F12").map(|k| k.code), Some(KeyCode::F(12)));
+        assert_eq!(
+            KeySpec::parse("-").map(|k| k.code),
+            Some(KeyCode::Char('-'))
+        );
+        assert_eq!(KeySpec::parse("Q-x"), None);
+        assert_eq!(KeySpec::parse("Enterprise"), None);
+    }
+
+    #[test]
+    fn shift_is_implied_by_printable_keys() {
+        let spec = KeySpec::parse("?").expect("valid");
+        assert!(spec.matches(&press(KeyCode::Char('?'), KeyModifiers::SHIFT)));
+        assert!(spec.matches(&press(KeyCode::Char('?'), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn shift_tab_matches_back_tab() {
+        let spec = KeySpec::parse("S-Tab").expect("valid");
+        // crossterm reports BackTab with Shift held
+        assert!(spec.matches(&press(KeyCode::BackTab, KeyModifiers::SHIFT)));
+        assert!(spec.matches(&press(KeyCode::BackTab, KeyModifiers::NONE)));
+        assert!(!spec.matches(&press(KeyCode::Tab, KeyModifiers::NONE)));
+        assert_eq!(spec.to_string(), "S-Tab");
+    }
+
+    #[test]
+    fn control_slash_is_what_the_terminal_sends() {
+        let sent = press(KeyCode::Char('7'), KeyModifiers::CONTROL);
+        for name in ["C-/", "C-_"] {
+            let spec = KeySpec::parse(name).expect("valid");
+            assert!(spec.matches(&sent), "{}", name);
+            assert_eq!(spec.to_string(), "C-/");
+        }
+    }
+
+    #[test]
+    fn emacs_cancel_only_closes_completions() {
+        let keys = KeyBindings::new(Preset::Emacs, &KeyOverrides::default());
+        let sent = press(KeyCode::Char('g'), KeyModifiers::CONTROL);
+        assert_eq!(
+            keys.actions(Mode::Input, &sent).collect::<Vec<_>>(),
+            vec![Action::CloseCompletions]
+        );
+    }
+
+    #[test]
+    fn emacs_undo_fires() {
+        let keys = KeyBindings::new(Preset::Emacs, &KeyOverrides::default());
+        let sent = press(KeyCode::Char('7'), KeyModifiers::CONTROL);
+        assert_eq!(
+            keys.actions(Mode::Builder, &sent).collect::<Vec<_>>(),
+            vec![Action::Undo]
+        );
+    }
+}
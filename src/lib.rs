@@ -0,0 +1,12 @@
+//! Ukulele chord shapes, the music theory behind them and ratatui widgets
+//! that draw them. The `uke-tui` binary is built on this library.
+
+pub mod audio;
+pub mod chordpro;
+pub mod chords;
+pub mod html;
+pub mod midi;
+pub mod svg;
+pub mod theme;
+pub mod theory;
+pub mod widgets;
@@ -0,0 +1,233 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use uke_tui::chords::{self, Chord, EMBEDDED_CHORDS, LibraryFile};
+
+use crate::paths;
+
+/// Where a layer of the library was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Embedded,
+    File(PathBuf),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Embedded => write!(f, "built in"),
+            Source::File(path) => write!(f, "{}", paths::display(path)),
+        }
+    }
+}
+
+/// One library file and what loading it did.
+pub struct Layer {
+    pub source: Source,
+    /// Chords read from this layer
+    pub count: usize,
+    /// Names this layer took over from earlier layers
+    pub overrides: Vec<String>,
+    /// Line numbers that are neither chords, comments nor blank
+    pub bad_lines: Vec<usize>,
+    /// Chord lines fretted past the end of the neck, with the first such fret
+    pub past_neck: Vec<(usize, u8)>,
+    /// Whether the file exists
+    pub found: bool,
+    /// Why an existing file could not be read
+    pub error: Option<String>,
+}
+
+/// The chord library: the embedded set with each library file layered on top.
+/// A chord name defined in a later layer replaces every entry of that name
+/// from earlier layers; new names are added.
+pub struct Library {
+    pub chords: Vec<Chord>,
+    /// Index into `layers` for each chord
+    origins: Vec<usize>,
+    pub layers: Vec<Layer>,
+}
+
+impl Library {
+    /// Load the embedded chords, then each file in `paths` in order. Missing
+    /// files are recorded but otherwise skipped.
+    pub fn load(paths: &[PathBuf]) -> Library {
+        let mut library = Library {
+            chords: Vec::new(),
+            origins: Vec::new(),
+            layers: Vec::new(),
+        };
+        library.add_layer(Source::Embedded, Some(Ok(EMBEDDED_CHORDS.to_string())));
+        for path in paths {
+            let data = match fs::read_to_string(path) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                other => Some(other.map_err(|e| e.to_string())),
+            };
+            library.add_layer(Source::File(path.clone()), data);
+        }
+        library
+    }
+
+    /// The library files, in layer order, for reloading.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.layers
+            .iter()
+            .filter_map(|layer| match &layer.source {
+                Source::File(path) => Some(path.clone()),
+                Source::Embedded => None,
+            })
+            .collect()
+    }
+
+    /// Whether `path` is one of the library's files, which exports must
+    /// never overwrite.
+    pub fn holds(&self, path: &Path) -> bool {
+        self.paths().iter().any(|layer| same_file(layer, path))
+    }
+
+    /// Which layer a library chord came from.
+    pub fn source_of(&self, chord: &Chord) -> Option<&Source> {
+        let i = self
+            .chords
+            .iter()
+            .position(|c| c.name == chord.name && c.frets == chord.frets)?;
+        Some(&self.layers[self.origins[i]].source)
+    }
+
+    /// Library files that couldn't be read or had lines that aren't chords,
+    /// one message per file.
+    pub fn problems(&self) -> Vec<String> {
+        self.layers
+            .iter()
+            .filter_map(|layer| {
+                if let Some(error) = &layer.error {
+                    return Some(format!("{}: {}", layer.source, error));
+                }
+                let mut found = Vec::new();
+                if let Some((first, rest)) = layer.bad_lines.split_first() {
+                    let mut lines = first.to_string();
+                    for n in rest {
+                        lines.push_str(&format!(", {}", n));
+                    }
+                    let s = if rest.is_empty() { "" } else { "s" };
+                    found.push(format!("line{} {}: expected `Name = G C E A`", s, lines));
+                }
+                for &(line, fret) in &layer.past_neck {
+                    found.push(format!("line {}: {}", line, chords::past_neck(fret)));
+                }
+                if found.is_empty() {
+                    return None;
+                }
+                Some(format!("{} {}", layer.source, found.join("; ")))
+            })
+            .collect()
+    }
+
+    /// Layer one source's text (`None` when the file doesn't exist) over the chords so far.
+    fn add_layer(&mut self, source: Source, data: Option<Result<String, String>>) {
+        let layer = self.layers.len();
+        let found = data.is_some();
+        let (parsed, error) = match data {
+            Some(Ok(data)) => (chords::parse_library(&data), None),
+            Some(Err(error)) => (LibraryFile::default(), Some(error)),
+            None => (LibraryFile::default(), None),
+        };
+        let LibraryFile {
+            chords: entries,
+            bad_lines,
+            past_neck,
+        } = parsed;
+
+        // Rebuild the list, putting each overridden name's new entries where
+        // its first old entry was so the library keeps its order.
+        let mut overrides: Vec<String> = Vec::new();
+        let mut placed = vec![false; entries.len()];
+        let mut chords = Vec::with_capacity(self.chords.len() + entries.len());
+        let mut origins = Vec::with_capacity(chords.capacity());
+        for (chord, origin) in self.chords.drain(..).zip(self.origins.drain(..)) {
+            let replaced_by: Vec<usize> = (0..entries.len())
+                .filter(|&i| chord.matches_name(&entries[i].name))
+                .collect();
+            if replaced_by.is_empty() {
+                chords.push(chord);
+                origins.push(origin);
+                continue;
+            }
+            if !overrides.contains(&chord.name) {
+                overrides.push(chord.name.clone());
+            }
+            for i in replaced_by {
+                if !placed[i] {
+                    placed[i] = true;
+                    chords.push(entries[i].clone());
+                    origins.push(layer);
+                }
+            }
+        }
+        for (entry, placed) in entries.iter().zip(&placed) {
+            if !placed {
+                chords.push(entry.clone());
+                origins.push(layer);
+            }
+        }
+        self.chords = chords;
+        self.origins = origins;
+        self.layers.push(Layer {
+            source,
+            count: entries.len(),
+            overrides,
+            bad_lines,
+            past_neck,
+            found,
+            error,
+        });
+    }
+}
+
+/// Whether `path` names the same file as `other`, even if spelled differently.
+pub fn same_file(path: &Path, other: &Path) -> bool {
+    path == other
+        || matches!(
+            (fs::canonicalize(path), fs::canonicalize(other)),
+            (Ok(a), Ok(b)) if a == b
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layered(text: &str) -> Library {
+        let mut library = Library {
+            chords: Vec::new(),
+            origins: Vec::new(),
+            layers: Vec::new(),
+        };
+        library.add_layer(Source::Embedded, Some(Ok(text.to_string())));
+        library
+    }
+
+    #[test]
+    fn frets_past_the_neck_are_refused_and_reported() {
+        let library = layered("C = 0 0 0 3\nC = 0 0 0 250\nD = 2 2 2 13\nnonsense\n");
+        assert_eq!(library.chords.len(), 1);
+        assert_eq!(library.layers[0].past_neck, vec![(2, 250), (3, 13)]);
+        assert_eq!(
+            library.problems(),
+            vec![
+                "built in line 4: expected `Name = G C E A`; \
+                 line 2: Fret 250 is past the end of the neck (12 frets); \
+                 line 3: Fret 13 is past the end of the neck (12 frets)"
+            ]
+        );
+    }
+
+    #[test]
+    fn the_last_fret_is_on_the_neck() {
+        let library = layered("C = 12 12 12 12\n");
+        assert_eq!(library.chords.len(), 1);
+        assert!(library.problems().is_empty());
+    }
+}
@@ -1,15 +1,351 @@
-mod chords;
+mod builder;
+mod complete;
+mod config;
+mod editor;
+mod export;
+mod grid;
+mod keys;
+mod library;
+mod paths;
+mod suggest;
 mod tui;
+mod watch;
 
-use chords::Chord;
-use std::process;
+use config::Config;
+use export::{FORMAT_NAMES, Format};
+use library::Library;
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+};
+use uke_tui::audio::{self, Stroke, Strumming};
+use uke_tui::midi::{self, Arrangement};
+
+const USAGE: &str = "\
+Usage: uke-tui [--library FILE]... [--song FILE]
+       uke-tui [--library FILE]... --format FORMAT [--output FILE] (CHORDS | --song FILE)
+       uke-tui [--library FILE]... --format midi --output FILE CHORDS [--tempo BPM] [--bars N]
+               [--time-signature N/D] [--strum-offset MS]
+       uke-tui [--library FILE]... --render-audio CHORDS FILE [--tempo BPM] [--strum PATTERN] [--bars N]
+
+Options:
+  --library FILE  layer another chord library over the configured ones (repeatable)
+  --format FORMAT export CHORDS (e.g. \"C, Am, F, G\") or a song as svg, html,
+                  markdown, text or midi instead of starting the TUI
+  --song FILE     export the chords and lyrics of a ChordPro song; without
+                  --format, show its chords and follow edits to it
+  --output FILE   write the export to FILE rather than standard output
+  --render-audio CHORDS FILE
+                  play CHORDS with plucked strings into a WAV file
+  --tempo BPM     quarter notes per minute, 4 to 1000 (default 90)
+  --strum PATTERN one bar of eighth notes: D/U strum down/up, 1-4 pick a string
+                  (1 = G), - rests; or down, up, down-up, island, arpeggio
+                  (default island, D-DU-UDU)
+  --bars N        bars each chord lasts (default 1)
+  --time-signature N/D
+                  beats per bar and beat length of MIDI exports (default 4/4)
+  --strum-offset MS
+                  milliseconds between strings in MIDI exports, 0 (together)
+                  to 1000 (default 15)
+  -h, --help      show this help";
+
+/// What to export.
+enum Input {
+    /// A progression, as typed into the input box
+    Chords(String),
+    /// A ChordPro file
+    Song(PathBuf),
+}
+
+/// Command-line options.
+struct Args {
+    /// Extra library files, layered last in the order given
+    libraries: Vec<PathBuf>,
+    /// Export `input` in this format instead of running the TUI
+    format: Option<Format>,
+    output: Option<PathBuf>,
+    input: Option<Input>,
+    /// Render `input` into this WAV file instead of running the TUI
+    audio: Option<PathBuf>,
+    strumming: Strumming,
+    /// MIDI settings given here, over the config's `[midi]`
+    midi: MidiOptions,
+}
+
+#[derive(Default)]
+struct MidiOptions {
+    tempo: Option<u32>,
+    time_signature: Option<(u8, u8)>,
+    bars: Option<u32>,
+    strum_offset: Option<u32>,
+}
+
+impl MidiOptions {
+    fn apply(&self, arrangement: &mut Arrangement) {
+        if let Some(tempo) = self.tempo {
+            arrangement.tempo = tempo;
+        }
+        if let Some(time_signature) = self.time_signature {
+            arrangement.time_signature = time_signature;
+        }
+        if let Some(bars) = self.bars {
+            arrangement.bars = bars;
+        }
+        if let Some(offset) = self.strum_offset {
+            arrangement.strum_offset = offset;
+        }
+    }
+}
+
+/// A whole number option's value, at least 1.
+fn count(name: &str, value: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|&n| n > 0)
+        .ok_or_else(|| format!("{} needs a positive number, not `{}`", name, value))
+}
+
+/// The value of an option given as `--name VALUE` or `--name=VALUE`.
+fn value(
+    arg: &str,
+    name: &str,
+    argv: &mut impl Iterator<Item = String>,
+) -> Result<Option<String>, String> {
+    if arg == name {
+        return argv
+            .next()
+            .map(Some)
+            .ok_or_else(|| format!("{} needs a value", name));
+    }
+    Ok(arg
+        .strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix('='))
+        .map(String::from))
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        libraries: Vec::new(),
+        format: None,
+        output: None,
+        input: None,
+        audio: None,
+        strumming: Strumming::default(),
+        midi: MidiOptions::default(),
+    };
+    let mut argv = env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => {
+                if let Some(path) = value(&arg, "--library", &mut argv)? {
+                    args.libraries.push(PathBuf::from(path));
+                } else if let Some(name) = value(&arg, "--format", &mut argv)? {
+                    let format = Format::named(&name).ok_or_else(|| {
+                        format!(
+                            "unknown format `{}` (expected {})",
+                            name,
+                            FORMAT_NAMES.join(", ")
+                        )
+                    })?;
+                    args.format = Some(format);
+                } else if let Some(path) = value(&arg, "--output", &mut argv)? {
+                    args.output = Some(PathBuf::from(path));
+                } else if let Some(chords) = value(&arg, "--render-audio", &mut argv)? {
+                    let path = argv
+                        .next()
+                        .ok_or("--render-audio needs chords and a file")?;
+                    args.audio = Some(PathBuf::from(path));
+                    if args.input.is_some() {
+                        return Err(second_input(&args));
+                    }
+                    args.input = Some(Input::Chords(chords));
+                } else if let Some(tempo) = value(&arg, "--tempo", &mut argv)? {
+                    args.strumming.tempo = count("--tempo", &tempo)?;
+                    if !midi::TEMPO_RANGE.contains(&args.strumming.tempo) {
+                        return Err(format!(
+                            "--tempo must be between {} and {}",
+                            midi::TEMPO_RANGE.start(),
+                            midi::TEMPO_RANGE.end()
+                        ));
+                    }
+                    args.midi.tempo = Some(args.strumming.tempo);
+                } else if let Some(pattern) = value(&arg, "--strum", &mut argv)? {
+                    args.strumming.pattern = Stroke::parse_pattern(&pattern)?;
+                } else if let Some(bars) = value(&arg, "--bars", &mut argv)? {
+                    args.strumming.bars = count("--bars", &bars)?;
+                    args.midi.bars = Some(args.strumming.bars);
+                } else if let Some(text) = value(&arg, "--time-signature", &mut argv)? {
+                    args.midi.time_signature = Some(midi::parse_time_signature(&text)?);
+                } else if let Some(offset) = value(&arg, "--strum-offset", &mut argv)? {
+                    let offset = offset
+                        .parse()
+                        .ok()
+                        .filter(|&ms| ms <= midi::MAX_STRUM_OFFSET)
+                        .ok_or_else(|| {
+                            format!(
+                                "--strum-offset needs a number of milliseconds up to {}, not `{}`",
+                                midi::MAX_STRUM_OFFSET,
+                                offset
+                            )
+                        })?;
+                    args.midi.strum_offset = Some(offset);
+                } else if args.input.is_some()
+                    && (arg == "--song" || arg.starts_with("--song=") || !arg.starts_with('-'))
+                {
+                    return Err(second_input(&args));
+                } else if let Some(path) = value(&arg, "--song", &mut argv)? {
+                    args.input = Some(Input::Song(PathBuf::from(path)));
+                } else if arg.starts_with('-') {
+                    return Err(format!("unexpected argument `{}`", arg));
+                } else {
+                    args.input = Some(Input::Chords(arg));
+                }
+            }
+        }
+    }
+    if args.format.is_some() && args.audio.is_some() {
+        return Err("give either --format or --render-audio".into());
+    }
+    if args.output.is_some() && args.audio.is_some() {
+        return Err("--render-audio writes to its own FILE; leave out --output".into());
+    }
+    let exporting = args.format.is_some() || args.audio.is_some();
+    if exporting && args.input.is_none()
+        || !exporting && matches!(args.input, Some(Input::Chords(_)))
+    {
+        return Err("--format goes with a list of chords or --song".into());
+    }
+    Ok(args)
+}
+
+/// The complaint about a second chord list or song: `--render-audio` brings
+/// its own chords, so it can't be combined with either.
+fn second_input(args: &Args) -> String {
+    if args.audio.is_some() {
+        "--render-audio takes its own chords; leave out --song and other chords".into()
+    } else {
+        "give either a list of chords or one --song".into()
+    }
+}
+
+/// Print an error and exit with a failure status.
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("Error: {}", message);
+    process::exit(1);
+}
+
+/// Stop before an export overwrites one of the chord library's files.
+fn refuse_library(path: &Path, library: &Library) {
+    if library.holds(path) {
+        fail(format!(
+            "{} is a chord library file; export somewhere else",
+            path.display()
+        ));
+    }
+}
+
+/// Play `chords` into a WAV file at `path` and exit, without starting the
+/// TUI or touching an audio device.
+fn render_audio(
+    chords: &str,
+    path: PathBuf,
+    strumming: &Strumming,
+    config: &Config,
+    library: &Library,
+) -> ! {
+    refuse_library(&path, library);
+    let sheet = export::progression(chords, library).unwrap_or_else(|e| fail(e));
+    let samples = audio::render(
+        sheet.chords.iter().map(|(_, c)| c),
+        config.tuning,
+        strumming,
+    );
+    if let Err(e) = fs::write(&path, audio::wav(&samples)) {
+        fail(format!("{}: {}", path.display(), e));
+    }
+    process::exit(0);
+}
+
+/// Write `input` in `format` and exit, without starting the TUI.
+fn export(
+    format: Format,
+    input: Input,
+    output: Option<PathBuf>,
+    config: &Config,
+    library: &Library,
+) -> ! {
+    let sheet = match input {
+        Input::Chords(chords) => export::progression(&chords, library).unwrap_or_else(|e| fail(e)),
+        Input::Song(path) => {
+            let text = fs::read_to_string(&path)
+                .unwrap_or_else(|e| fail(format!("{}: {}", path.display(), e)));
+            let (mut sheet, missing) = export::song(&text, library);
+            if sheet.song.title.is_none() {
+                sheet.song.title = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+            }
+            for name in missing {
+                eprintln!("Warning: chord not found: {}", name);
+            }
+            sheet
+        }
+    };
+    if let Some(path) = &output {
+        refuse_library(path, library);
+    }
+    let written = match output {
+        Some(path) => export::write(format, &sheet, config, &path),
+        None => export::render(format, &sheet, config)
+            .and_then(|bytes| std::io::stdout().write_all(&bytes)),
+    };
+    if let Err(e) = written {
+        fail(e);
+    }
+    process::exit(0);
+}
 
 fn main() {
-    // Load all chords
-    let chords = Chord::load_all();
+    let args = parse_args().unwrap_or_else(|e| {
+        eprintln!("Error: {}\n\n{}", e, USAGE);
+        process::exit(2);
+    });
+
+    // Settings first: they say where the chord libraries live
+    let (mut config, warning) = Config::load();
+    args.midi.apply(&mut config.midi);
+
+    // Load all chords, built-in ones first with each library file layered on top
+    let library = Library::load(&config.library_layers(&args.libraries));
+
+    // Problems with either are shown in the footer rather than stopping startup
+    let problems: Vec<String> = warning.into_iter().chain(library.problems()).collect();
+
+    if args.format.is_some() || args.audio.is_some() {
+        for problem in &problems {
+            eprintln!("Warning: {}", problem);
+        }
+    }
+    if let (Some(path), Some(Input::Chords(chords))) = (args.audio, &args.input) {
+        render_audio(chords, path, &args.strumming, &config, &library);
+    }
+    let song = match (args.format, args.input) {
+        (Some(format), Some(input)) => export(format, input, args.output, &config, &library),
+        (None, Some(Input::Song(path))) => Some(path),
+        _ => None,
+    };
+    let status = (!problems.is_empty()).then(|| problems.join("; "));
 
     // Launch TUI app
-    let app = tui::App::new(chords);
+    let mut app = tui::App::new(library, config, status);
+    if let Some(path) = song {
+        app.open_song(path);
+    }
     if let Err(e) = tui::run_tui(app) {
         eprintln!("Error: {}", e);
         process::exit(1);
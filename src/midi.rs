@@ -0,0 +1,528 @@
+use std::ops::RangeInclusive;
+
+use crate::chords::Chord;
+
+/// Resolution of written files: ticks per quarter note.
+pub const TICKS_PER_QUARTER: u16 = 480;
+
+/// Tempos a file can hold: MIDI stores a quarter note's length in three
+/// bytes of microseconds, which runs out below 4 per minute.
+pub const TEMPO_RANGE: RangeInclusive<u32> = 4..=1000;
+/// Longest delay between strings, in milliseconds.
+pub const MAX_STRUM_OFFSET: u32 = 1000;
+
+/// General MIDI program 25, Acoustic Guitar (nylon): the nearest a DAW has
+/// to a ukulele.
+const PROGRAM: u8 = 24;
+const VELOCITY: u8 = 80;
+/// Largest gap between events a file can store: four seven-bit bytes.
+const MAX_DELTA: u32 = 0x0FFF_FFFF;
+
+/// How a progression is laid out in a MIDI file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arrangement {
+    /// Quarter notes per minute
+    pub tempo: u32,
+    /// Beats per bar and the note value of a beat: (6, 8) is 6/8
+    pub time_signature: (u8, u8),
+    /// Bars each chord lasts
+    pub bars: u32,
+    /// Delay between neighbouring strings as a chord is strummed G to A, in
+    /// milliseconds; 0 sounds them together
+    pub strum_offset: u32,
+}
+
+impl Default for Arrangement {
+    fn default() -> Self {
+        Arrangement {
+            tempo: 90,
+            time_signature: (4, 4),
+            bars: 1,
+            strum_offset: 15,
+        }
+    }
+}
+
+/// Read a time signature such as `3/4` or `6/8`. The beat must be a whole,
+/// half, quarter… down to a thirty-second note, as MIDI stores it as a power
+/// of two.
+pub fn parse_time_signature(text: &str) -> Result<(u8, u8), String> {
+    let error = || format!("time signature `{}` isn't like 4/4 or 6/8", text);
+    let (beats, beat) = text.trim().split_once('/').ok_or_else(error)?;
+    let beats: u8 = beats.trim().parse().map_err(|_| error())?;
+    let beat: u8 = beat.trim().parse().map_err(|_| error())?;
+    if !writable(beats, beat) {
+        return Err(error());
+    }
+    Ok((beats, beat))
+}
+
+/// Whether MIDI can store a time signature: at least one beat a bar, and a
+/// beat from a whole note down to a thirty-second.
+fn writable(beats: u8, beat: u8) -> bool {
+    beats > 0 && beat.is_power_of_two() && beat <= 32
+}
+
+/// One sounding string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Note {
+    /// In ticks from the start of the track
+    pub start: u32,
+    pub length: u32,
+    /// 0 = G … 3 = A, written as the MIDI channel so each string is its own
+    /// voice
+    pub string: u8,
+    /// MIDI note number
+    pub key: u8,
+    pub velocity: u8,
+}
+
+/// The contents of a one-track MIDI file, as written or read back.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sequence {
+    pub ticks_per_quarter: u16,
+    /// Quarter notes per minute
+    pub tempo: u32,
+    pub time_signature: (u8, u8),
+    /// Each chord's name at the tick it starts, as marker events
+    pub markers: Vec<(u32, String)>,
+    /// Ordered by start, then string
+    pub notes: Vec<Note>,
+}
+
+impl Sequence {
+    /// `chords` one after another, each held for `arrangement.bars` bars:
+    /// one note per sounding string at the tuning's own pitch, so re-entrant
+    /// and low-G tunings keep their octaves. Strings start
+    /// `arrangement.strum_offset` apart, G first, and all stop as the next
+    /// chord starts. Fails on a time signature MIDI can't store.
+    pub fn arrange<'a>(
+        chords: impl IntoIterator<Item = (&'a str, &'a Chord)>,
+        tuning: [u8; 4],
+        arrangement: &Arrangement,
+    ) -> Result<Sequence, String> {
+        let ticks = u32::from(TICKS_PER_QUARTER);
+        let tempo = arrangement
+            .tempo
+            .clamp(*TEMPO_RANGE.start(), *TEMPO_RANGE.end());
+        let (beats, beat) = arrangement.time_signature;
+        if !writable(beats, beat) {
+            return Err(format!(
+                "time signature {}/{} can't be written to MIDI",
+                beats, beat
+            ));
+        }
+        let bar = u32::from(beats) * ticks * 4 / u32::from(beat);
+        let length = bar.saturating_mul(arrangement.bars.max(1)).min(MAX_DELTA);
+        // Milliseconds to ticks: a quarter note lasts 60000 / tempo ms
+        let offset = u64::from(arrangement.strum_offset.min(MAX_STRUM_OFFSET))
+            * u64::from(ticks)
+            * u64::from(tempo)
+            / 60_000;
+        let offset = u32::try_from(offset).unwrap_or(u32::MAX);
+
+        let mut sequence = Sequence {
+            ticks_per_quarter: TICKS_PER_QUARTER,
+            tempo,
+            time_signature: arrangement.time_signature,
+            ..Sequence::default()
+        };
+        for (n, (name, chord)) in chords.into_iter().enumerate() {
+            let start = (n as u32).saturating_mul(length);
+            sequence.markers.push((start, name.to_string()));
+            let sounding = (0..4).filter_map(|string| {
+                chord.frets[string].map(|fret| (string as u8, tuning[string] + fret))
+            });
+            for (played, (string, key)) in sounding.enumerate() {
+                let delay = (played as u32).saturating_mul(offset).min(length - 1);
+                sequence.notes.push(Note {
+                    start: start.saturating_add(delay),
+                    length: length - delay,
+                    string,
+                    key,
+                    velocity: VELOCITY,
+                });
+            }
+        }
+        sequence.notes.sort_by_key(|note| (note.start, note.string));
+        Ok(sequence)
+    }
+
+    /// A format 0 Standard MIDI file: tempo, time signature and a nylon
+    /// guitar on channels 1-4, then the markers and notes. The tempo is
+    /// clamped to `TEMPO_RANGE`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (beats, beat) = self.time_signature;
+        let tempo = self.tempo.clamp(*TEMPO_RANGE.start(), *TEMPO_RANGE.end());
+        let mut events: Vec<(u32, u8, Vec<u8>)> = vec![
+            (0, 0, meta(0x51, &(60_000_000 / tempo).to_be_bytes()[1..])),
+            (
+                0,
+                0,
+                // Beat as a power of two; a metronome click per beat
+                meta(
+                    0x58,
+                    &[beats, beat.trailing_zeros() as u8, 96 / beat.max(1), 8],
+                ),
+            ),
+        ];
+        for channel in 0..4 {
+            events.push((0, 0, vec![0xC0 | channel, PROGRAM]));
+        }
+        for (tick, name) in &self.markers {
+            events.push((*tick, 0, meta(0x06, name.as_bytes())));
+        }
+        for note in &self.notes {
+            let channel = note.string & 0x0F;
+            events.push((note.start, 2, vec![0x90 | channel, note.key, note.velocity]));
+            events.push((
+                note.start.saturating_add(note.length),
+                1,
+                vec![0x80 | channel, note.key, 0],
+            ));
+        }
+        // Notes ending on a tick stop before those starting on it
+        events.sort_by_key(|&(tick, order, _)| (tick, order));
+        let end = events.last().map_or(0, |&(tick, _, _)| tick);
+        events.push((end, 3, meta(0x2F, &[])));
+
+        let mut track = Vec::new();
+        let mut now = 0;
+        for (tick, _, data) in events {
+            write_vlq(&mut track, (tick - now).min(MAX_DELTA));
+            track.extend_from_slice(&data);
+            now = tick;
+        }
+
+        let mut out = Vec::with_capacity(22 + track.len());
+        out.extend_from_slice(b"MThd");
+        out.extend_from_slice(&6u32.to_be_bytes());
+        out.extend_from_slice(&0u16.to_be_bytes()); // format 0
+        out.extend_from_slice(&1u16.to_be_bytes()); // one track
+        out.extend_from_slice(&self.ticks_per_quarter.to_be_bytes());
+        out.extend_from_slice(b"MTrk");
+        out.extend_from_slice(&(track.len() as u32).to_be_bytes());
+        out.extend_from_slice(&track);
+        out
+    }
+
+    /// Read a Standard MIDI file back, such as one `to_bytes` wrote. Tracks
+    /// are merged, the first tempo and time signature win, and a note's
+    /// channel is taken as its string. Files without a tempo or time
+    /// signature get MIDI's defaults, 120 and 4/4.
+    pub fn parse(bytes: &[u8]) -> Result<Sequence, String> {
+        let mut file = Reader { bytes, at: 0 };
+        if file.take(4)? != b"MThd" {
+            return Err("not a MIDI file".into());
+        }
+        let length = file.u32()? as usize;
+        let header = file.take(length)?;
+        let division = u16::from_be_bytes(
+            header
+                .get(4..6)
+                .and_then(|b| b.try_into().ok())
+                .ok_or("truncated MIDI header")?,
+        );
+        if division & 0x8000 != 0 {
+            return Err("SMPTE timing isn't supported".into());
+        }
+
+        let mut sequence = Sequence {
+            ticks_per_quarter: division,
+            ..Sequence::default()
+        };
+        let mut tempo = None;
+        let mut time_signature = None;
+        while file.at < file.bytes.len() {
+            let kind = file.take(4)?;
+            let length = file.u32()? as usize;
+            let chunk = file.take(length)?;
+            if kind == b"MTrk" {
+                read_track(chunk, &mut sequence, &mut tempo, &mut time_signature)?;
+            }
+        }
+        sequence.tempo = tempo.unwrap_or(120);
+        sequence.time_signature = time_signature.unwrap_or((4, 4));
+        sequence.markers.sort_by_key(|&(tick, _)| tick);
+        sequence.notes.sort_by_key(|note| (note.start, note.string));
+        Ok(sequence)
+    }
+}
+
+/// A meta event of `kind`.
+fn meta(kind: u8, data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0xFF, kind];
+    write_vlq(&mut out, data.len() as u32);
+    out.extend_from_slice(data);
+    out
+}
+
+/// A variable-length quantity: seven bits a byte, most significant first,
+/// the top bit set on all but the last.
+fn write_vlq(out: &mut Vec<u8>, mut value: u32) {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.extend(bytes.iter().rev());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .at
+            .checked_add(n)
+            .filter(|&end| end <= self.bytes.len());
+        let Some(end) = end else {
+            return Err("truncated MIDI file".into());
+        };
+        let out = &self.bytes[self.at..end];
+        self.at = end;
+        Ok(out)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn vlq(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | u32::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("variable-length number longer than four bytes".into())
+    }
+}
+
+/// Add one track's events to `sequence`.
+fn read_track(
+    bytes: &[u8],
+    sequence: &mut Sequence,
+    tempo: &mut Option<u32>,
+    time_signature: &mut Option<(u8, u8)>,
+) -> Result<(), String> {
+    let mut track = Reader { bytes, at: 0 };
+    let mut now = 0u32;
+    let mut running = None;
+    // Notes sounding on each channel and key: start and velocity, oldest
+    // first, as two strings can share a pitch
+    let mut sounding: Vec<((u8, u8), u32, u8)> = Vec::new();
+    while track.at < bytes.len() {
+        now = now.saturating_add(track.vlq()?);
+        let mut status = track.byte()?;
+        let first = if status < 0x80 {
+            // Running status: the byte was data for the previous status
+            let data = status;
+            status = running.ok_or("MIDI data without a status byte")?;
+            Some(data)
+        } else {
+            None
+        };
+        match status {
+            0xFF => {
+                let kind = track.byte()?;
+                let length = track.vlq()? as usize;
+                let data = track.take(length)?;
+                match (kind, data) {
+                    (0x51, &[a, b, c]) if tempo.is_none() => {
+                        let micros = u32::from_be_bytes([0, a, b, c]).max(1);
+                        *tempo = Some((60_000_000 + micros / 2) / micros);
+                    }
+                    (0x58, &[beats, power, ..]) if time_signature.is_none() => {
+                        *time_signature = Some((beats, 1u8.checked_shl(power.into()).unwrap_or(0)));
+                    }
+                    (0x06, name) => sequence
+                        .markers
+                        .push((now, String::from_utf8_lossy(name).into_owned())),
+                    (0x2F, _) => break,
+                    _ => {}
+                }
+            }
+            0xF0 | 0xF7 => {
+                let length = track.vlq()? as usize;
+                track.take(length)?;
+            }
+            0x80..=0xEF => {
+                running = Some(status);
+                let first = match first {
+                    Some(data) => data,
+                    None => track.byte()?,
+                };
+                let second = if matches!(status & 0xF0, 0xC0 | 0xD0) {
+                    0
+                } else {
+                    track.byte()?
+                };
+                let channel = status & 0x0F;
+                match status & 0xF0 {
+                    0x90 if second > 0 => sounding.push(((channel, first), now, second)),
+                    0x80 | 0x90 => {
+                        if let Some(i) = sounding
+                            .iter()
+                            .position(|&(at, _, _)| at == (channel, first))
+                        {
+                            let (_, start, velocity) = sounding.remove(i);
+                            sequence.notes.push(Note {
+                                start,
+                                length: now - start,
+                                string: channel,
+                                key: first,
+                                velocity,
+                            });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            other => return Err(format!("unexpected MIDI status byte {:#04X}", other)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOW_G: [u8; 4] = [55, 60, 64, 69];
+
+    fn chord(name: &str, frets: &str) -> Chord {
+        Chord::from_string(name, frets).expect("valid chord")
+    }
+
+    #[test]
+    fn round_trips_through_a_file() {
+        let c = chord("C", "0 0 0 3");
+        let am = chord("Am", "2 0 0 X");
+        let arrangement = Arrangement {
+            tempo: 100,
+            time_signature: (3, 4),
+            bars: 2,
+            strum_offset: 20,
+        };
+        let sequence =
+            Sequence::arrange([("C", &c), ("Am", &am)], LOW_G, &arrangement).expect("arranges");
+        let back = Sequence::parse(&sequence.to_bytes()).expect("parses");
+        assert_eq!(back, sequence);
+
+        assert_eq!(back.ticks_per_quarter, TICKS_PER_QUARTER);
+        assert_eq!(back.tempo, 100);
+        assert_eq!(back.time_signature, (3, 4));
+        // Two bars of 3/4 per chord
+        assert_eq!(back.markers, vec![(0, "C".into()), (2880, "Am".into())]);
+
+        // Low G sounds an octave below the other strings' G; the muted A
+        // string of Am plays nothing
+        let keys: Vec<(u32, u8, u8)> = back
+            .notes
+            .iter()
+            .map(|n| (n.start, n.string, n.key))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                (0, 0, 55),
+                (16, 1, 60),
+                (32, 2, 64),
+                (48, 3, 72),
+                (2880, 0, 57),
+                (2896, 1, 60),
+                (2912, 2, 64),
+            ]
+        );
+        // Every string stops as the next chord starts
+        assert!(back.notes.iter().all(|n| (n.start + n.length) % 2880 == 0));
+    }
+
+    #[test]
+    fn keeps_re_entrant_octaves() {
+        let c = chord("C", "0 0 0 3");
+        let sequence = Sequence::arrange([("C", &c)], [67, 60, 64, 69], &Arrangement::default())
+            .expect("arranges");
+        let keys: Vec<u8> = sequence.notes.iter().map(|n| n.key).collect();
+        assert_eq!(keys, vec![67, 60, 64, 72]);
+    }
+
+    #[test]
+    fn survives_extreme_settings() {
+        let c = chord("C", "0 0 0 3");
+        let arrangement = Arrangement {
+            tempo: 1,
+            time_signature: (4, 4),
+            bars: u32::MAX,
+            strum_offset: u32::MAX,
+        };
+        let sequence =
+            Sequence::arrange([("C", &c), ("C", &c)], LOW_G, &arrangement).expect("arranges");
+        let back = Sequence::parse(&sequence.to_bytes()).expect("parses");
+        assert_eq!(back.tempo, *TEMPO_RANGE.start());
+        assert_eq!(back.notes.len(), 8);
+    }
+
+    #[test]
+    fn refuses_time_signatures_midi_cannot_store() {
+        let c = chord("C", "0 0 0 3");
+        for time_signature in [(0, 4), (4, 0), (3, 3), (7, 12), (4, 64)] {
+            let arrangement = Arrangement {
+                time_signature,
+                ..Arrangement::default()
+            };
+            assert!(
+                Sequence::arrange([("C", &c)], LOW_G, &arrangement).is_err(),
+                "{:?}",
+                time_signature
+            );
+        }
+    }
+
+    #[test]
+    fn writes_the_beat_as_a_power_of_two() {
+        let c = chord("C", "0 0 0 3");
+        let arrangement = Arrangement {
+            time_signature: (6, 8),
+            ..Arrangement::default()
+        };
+        let bytes = Sequence::arrange([("C", &c)], LOW_G, &arrangement)
+            .expect("arranges")
+            .to_bytes();
+        let at = bytes
+            .windows(3)
+            .position(|w| w == [0xFF, 0x58, 4])
+            .expect("time signature event");
+        assert_eq!(bytes[at + 3..at + 7], [6, 3, 12, 8]);
+    }
+
+    #[test]
+    fn reads_time_signatures() {
+        assert_eq!(parse_time_signature("6/8"), Ok((6, 8)));
+        assert_eq!(parse_time_signature(" 3 / 4 "), Ok((3, 4)));
+        assert!(parse_time_signature("5/3").is_err());
+        assert!(parse_time_signature("0/4").is_err());
+        assert!(parse_time_signature("4").is_err());
+    }
+
+    #[test]
+    fn rejects_broken_files() {
+        assert!(Sequence::parse(b"RIFF").is_err());
+        let bytes = Sequence::arrange([], LOW_G, &Arrangement::default())
+            .expect("arranges")
+            .to_bytes();
+        assert!(Sequence::parse(&bytes[..bytes.len() - 2]).is_err());
+    }
+}
@@ -0,0 +1,51 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+/// Directory for data the app keeps between sessions, such as input history:
+/// `$XDG_STATE_HOME/uke-tui`, falling back to `~/.local/state/uke-tui`.
+pub fn state_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_STATE_HOME", ".local/state")
+}
+
+/// Directory for user settings and the user's chord library:
+/// `$XDG_CONFIG_HOME/uke-tui`, falling back to `~/.config/uke-tui`.
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// System-wide config directory: the first of `$XDG_CONFIG_DIRS`, else `/etc/xdg`.
+pub fn system_config_dir() -> PathBuf {
+    env::var_os("XDG_CONFIG_DIRS")
+        .and_then(|dirs| env::split_paths(&dirs).find(|p| p.is_absolute()))
+        .unwrap_or_else(|| PathBuf::from("/etc/xdg"))
+        .join("uke-tui")
+}
+
+/// A path for display, with the home directory shortened to `~`.
+pub fn display(path: &Path) -> String {
+    if let Some(home) = env::var_os("HOME")
+        && let Ok(rest) = path.strip_prefix(home)
+    {
+        return format!("~/{}", rest.display());
+    }
+    path.display().to_string()
+}
+
+/// Expand a leading `~/` to the home directory, as shells do for config values.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Resolve an XDG base directory, ignoring relative values as the spec requires.
+fn xdg_dir(var: &str, home_fallback: &str) -> Option<PathBuf> {
+    let base = env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(home_fallback)))?;
+    Some(base.join("uke-tui"))
+}
@@ -0,0 +1,223 @@
+use uke_tui::chords::Chord;
+
+/// Common ways of writing a quality that the library spells differently.
+const QUALITY_SYNONYMS: [(&str, &str); 34] = [
+    ("M", ""),
+    ("maj", ""),
+    ("major", ""),
+    ("min", "m"),
+    ("minor", "m"),
+    ("mi", "m"),
+    ("-", "m"),
+    ("M7", "maj7"),
+    ("ma7", "maj7"),
+    ("Δ", "maj7"),
+    ("Δ7", "maj7"),
+    ("M9", "maj9"),
+    ("Δ9", "maj9"),
+    ("dom7", "7"),
+    ("min7", "m7"),
+    ("-7", "m7"),
+    ("min9", "m9"),
+    ("-9", "m9"),
+    ("min6", "m6"),
+    ("-6", "m6"),
+    ("o", "dim"),
+    ("°", "dim"),
+    ("o7", "dim7"),
+    ("°7", "dim7"),
+    ("ø", "m7b5"),
+    ("ø7", "m7b5"),
+    ("m7-5", "m7b5"),
+    ("+", "aug"),
+    ("7#5", "7+5"),
+    ("aug7", "7+5"),
+    ("7-5", "7b5"),
+    ("sus", "sus4"),
+    ("69", "6/9"),
+    ("add2", "add9"),
+];
+
+/// Same meaning, but compared without regard to case.
+const QUALITY_SYNONYMS_ANY_CASE: [(&str, &str); 4] = [
+    ("mmaj7", "mM7"),
+    ("m(maj7)", "mM7"),
+    ("minmaj7", "mM7"),
+    ("6add9", "6/9"),
+];
+
+/// Suggest up to `limit` library chords for an unknown name, best first.
+///
+/// Music-aware rewrites (quality synonyms, `H` for `B`, `M`/`m` mix-ups and a
+/// forgotten `#` or `b`) rank ahead of plain edit-distance matches.
+pub fn suggest(input: &str, chords: &[Chord], limit: usize) -> Vec<String> {
+    let input = input.trim();
+    let mut scored: Vec<(usize, String)> = Vec::new();
+
+    let mut offer = |score: usize, name: &str| {
+        if let Some(chord) = chords.iter().find(|c| c.matches_name(name))
+            && !scored.iter().any(|(_, n)| chord.matches_name(n))
+        {
+            scored.push((score, name.to_string()));
+        }
+    };
+
+    for (score, candidate) in rewrites(input) {
+        offer(score, &candidate);
+    }
+
+    // Edit distance over every name and alias
+    let max_distance = (input.chars().count() / 3).max(2);
+    let mut nearest: Vec<(usize, &str)> = chords
+        .iter()
+        .flat_map(|c| c.names())
+        .map(|n| (edit_distance(input, n), n))
+        .filter(|&(d, _)| d <= max_distance)
+        .collect();
+    nearest.sort();
+    for (distance, name) in nearest {
+        offer(2 + distance * 2, name);
+    }
+
+    scored.sort_by_key(|(score, _)| *score);
+    scored.into_iter().take(limit).map(|(_, n)| n).collect()
+}
+
+/// Candidate spellings of `input` with a cost for each correction.
+fn rewrites(input: &str) -> Vec<(usize, String)> {
+    let mut chars = input.chars();
+    let Some(first) = chars.next() else {
+        return Vec::new();
+    };
+    let rest = chars.as_str();
+
+    // German-style H for B, and a lowercase root letter
+    let letter = match first.to_ascii_uppercase() {
+        'H' => 'B',
+        c => c,
+    };
+    let base_cost = usize::from(letter != first);
+    let (accidental, quality) = match rest.chars().next() {
+        Some(c @ ('#' | 'b' | '♯' | '♭')) => (
+            if c == '#' || c == '♯' { "#" } else { "b" },
+            &rest[c.len_utf8()..],
+        ),
+        _ => ("", rest),
+    };
+
+    let mut qualities: Vec<(usize, String)> = vec![(0, quality.to_string())];
+    for (from, to) in QUALITY_SYNONYMS {
+        if quality == from {
+            qualities.push((1, to.to_string()));
+        }
+    }
+    for (from, to) in QUALITY_SYNONYMS_ANY_CASE {
+        if quality.eq_ignore_ascii_case(from) {
+            qualities.push((1, to.to_string()));
+        }
+    }
+    // M and m swapped at the start of the quality
+    if let Some(tail) = quality.strip_prefix('M') {
+        qualities.push((2, format!("m{}", tail)));
+        qualities.push((2, format!("maj{}", tail)));
+    } else if let Some(tail) = quality.strip_prefix('m') {
+        qualities.push((2, format!("M{}", tail)));
+    }
+
+    let mut roots = vec![(base_cost, format!("{}{}", letter, accidental))];
+    if accidental.is_empty() {
+        roots.push((base_cost + 2, format!("{}#", letter)));
+        roots.push((base_cost + 2, format!("{}b", letter)));
+    }
+
+    let mut out = Vec::new();
+    for (root_cost, root) in &roots {
+        for (quality_cost, quality) in &qualities {
+            let cost = root_cost + quality_cost;
+            if cost > 0 {
+                out.push((cost, format!("{}{}", root, quality)));
+            }
+        }
+    }
+    out
+}
+
+/// Levenshtein distance between two strings, counted in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != cb);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library() -> Vec<Chord> {
+        [
+            "C = 0 0 0 3",
+            "Cm7 = 3 3 3 3",
+            "Cmaj7 = 0 0 0 2",
+            "Bb = 3 2 1 1",
+            "C#m = 1 1 0 4",
+            "F#7 = 3 4 2 4",
+        ]
+        .iter()
+        .filter_map(|line| line.split_once('='))
+        .filter_map(|(name, frets)| Chord::from_string(name.trim(), frets.trim()))
+        .collect()
+    }
+
+    fn costs(input: &str) -> Vec<(usize, String)> {
+        let mut out = rewrites(input);
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn counts_edits_in_chars() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("Cm7", "Cm7"), 0);
+        assert_eq!(edit_distance("Cm7", "Cm"), 1);
+        assert_eq!(edit_distance("Cm", "Cm7"), 1);
+        assert_eq!(edit_distance("Cmaj7", "CM7"), 3);
+        assert_eq!(edit_distance("C°7", "Co7"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn rewrites_music_aware_mistakes() {
+        let found = costs("CM7");
+        assert!(found.contains(&(1, "Cmaj7".into())));
+        assert!(found.contains(&(2, "Cm7".into())));
+        assert!(found.contains(&(2, "C#M7".into())));
+
+        // H is German for B; a lowercase root costs one
+        assert!(costs("H").contains(&(1, "B".into())));
+        assert!(costs("bb").contains(&(1, "Bb".into())));
+        // A written accidental is kept, and only synonyms are offered
+        assert!(costs("F♯7").iter().all(|(_, n)| n.starts_with("F#")));
+        assert!(costs("Cminmaj7").contains(&(1, "CmM7".into())));
+        // Nothing costs zero: the input itself isn't a rewrite
+        assert!(costs("C").iter().all(|&(cost, _)| cost > 0));
+        assert!(rewrites("").is_empty());
+    }
+
+    #[test]
+    fn ranks_rewrites_before_near_misses() {
+        let chords = library();
+        assert_eq!(suggest("CM7", &chords, 2), ["Cmaj7", "Cm7"]);
+        assert_eq!(suggest("Hb", &chords, 1), ["Bb"]);
+        assert_eq!(suggest("DbM", &chords, 1), ["Dbm"]);
+        assert_eq!(suggest("F7", &chords, 1), ["F#7"]);
+        assert!(suggest("zzzzzz", &chords, 3).is_empty());
+    }
+}
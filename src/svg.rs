@@ -0,0 +1,435 @@
+use std::fmt::Write;
+
+use crate::theory::Tone;
+use crate::widgets::{ChordDiagram, Orientation, Part, Sketch, inlay};
+
+/// Distance between neighbouring strings, in SVG user units (pixels).
+const STRING_GAP: f32 = 20.0;
+/// Distance between neighbouring frets.
+const FRET_GAP: f32 = 28.0;
+/// Room above a diagram for its title.
+const TITLE_HEIGHT: f32 = 26.0;
+/// Blank space around a diagram's drawing.
+const PADDING: f32 = 12.0;
+/// Space between diagrams in a grid.
+const SPACING: f32 = 16.0;
+const DOT_RADIUS: f32 = 8.0;
+
+/// A diagram drawn from the origin, and the room it takes.
+#[derive(Debug, Clone, Default)]
+pub struct Drawing {
+    pub width: f32,
+    pub height: f32,
+    /// SVG elements, without the enclosing `<svg>`
+    pub body: String,
+}
+
+impl Drawing {
+    /// A standalone SVG document.
+    pub fn document(&self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+             viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">\n{}</svg>\n",
+            self.body,
+            w = num(self.width),
+            h = num(self.height),
+        )
+    }
+}
+
+/// One chord as a standalone SVG document.
+pub fn chord(diagram: &ChordDiagram) -> String {
+    drawing(diagram).document()
+}
+
+/// Several chords as one SVG document, laid out left to right and wrapped
+/// into rows no wider than `max_width` (a single diagram may be wider).
+/// Give the diagrams a shared window to line them up, as the grid does.
+pub fn grid(diagrams: &[ChordDiagram], max_width: f32) -> String {
+    let mut out = Drawing::default();
+    let (mut x, mut y, mut row_height) = (0.0, 0.0, 0.0f32);
+    for diagram in diagrams {
+        let cell = drawing(diagram);
+        if x > 0.0 && x + cell.width > max_width {
+            x = 0.0;
+            y += row_height + SPACING;
+            row_height = 0.0;
+        }
+        let _ = writeln!(
+            out.body,
+            "<g transform=\"translate({},{})\">\n{}</g>",
+            num(x),
+            num(y),
+            cell.body
+        );
+        out.width = out.width.max(x + cell.width);
+        out.height = out.height.max(y + cell.height);
+        row_height = row_height.max(cell.height);
+        x += cell.width + SPACING;
+    }
+    out.document()
+}
+
+/// Where things go on a diagram: strings and frets are counted from the top
+/// string as drawn and from the nut, so both orientations share one drawing
+/// routine.
+struct Frame {
+    vertical: bool,
+    /// Frets run right to left, the nut on the right
+    mirrored: bool,
+    /// Frets in the window
+    frets: f32,
+    /// Where the top string meets the nut, or the right end of a mirrored
+    /// neck
+    x: f32,
+    y: f32,
+}
+
+impl Frame {
+    /// The point `string` strings below the top one and `fret` frets up
+    /// from the nut; fractions fall between them and negative frets lie
+    /// beyond the nut.
+    fn at(&self, string: f32, fret: f32) -> (String, String) {
+        let (x, y) = if self.vertical {
+            (self.x + string * STRING_GAP, self.y + fret * FRET_GAP)
+        } else if self.mirrored {
+            (
+                self.x + (self.frets - fret) * FRET_GAP,
+                self.y + string * STRING_GAP,
+            )
+        } else {
+            (self.x + fret * FRET_GAP, self.y + string * STRING_GAP)
+        };
+        (num(x), num(y))
+    }
+
+    fn line(&self, out: &mut String, from: (f32, f32), to: (f32, f32), width: f32) {
+        let (x1, y1) = self.at(from.0, from.1);
+        let (x2, y2) = self.at(to.0, to.1);
+        let _ = writeln!(
+            out,
+            "<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"black\" stroke-width=\"{}\"/>",
+            num(width)
+        );
+    }
+
+    /// Text centred on a point, or starting there when `anchor` is "start".
+    fn text(
+        &self,
+        out: &mut String,
+        at: (f32, f32),
+        size: f32,
+        anchor: &str,
+        attrs: &str,
+        text: &str,
+    ) {
+        let (x, y) = self.at(at.0, at.1);
+        let _ = writeln!(
+            out,
+            "<text x=\"{x}\" y=\"{y}\" font-size=\"{}\" text-anchor=\"{anchor}\" \
+             dominant-baseline=\"central\"{attrs}>{}</text>",
+            num(size),
+            escape(text)
+        );
+    }
+}
+
+/// Draw a diagram the way the terminal widget lays it out: the same window,
+/// orientation, mirroring, string order and labels.
+pub fn drawing(diagram: &ChordDiagram) -> Drawing {
+    let Sketch {
+        title,
+        strings,
+        frets,
+        marks,
+        window: (start, end),
+        view,
+    } = diagram.sketch();
+    let vertical = view.orientation == Orientation::Vertical;
+    let mirrored = view.frets_reversed();
+    let span = f32::from(end - start + 1);
+
+    // Room for the string names and markers before the nut, and the
+    // position label or fret numbers beside the neck
+    let (frame, neck_width, neck_height) = if vertical {
+        let frame = Frame {
+            vertical,
+            mirrored,
+            frets: span,
+            x: PADDING + 8.0,
+            y: TITLE_HEIGHT + 40.0,
+        };
+        (frame, 3.0 * STRING_GAP + 48.0, 40.0 + span * FRET_GAP)
+    } else {
+        let frame = Frame {
+            vertical,
+            mirrored,
+            frets: span,
+            x: if mirrored { PADDING } else { PADDING + 48.0 },
+            y: TITLE_HEIGHT + 22.0,
+        };
+        (frame, 48.0 + span * FRET_GAP, 22.0 + 3.0 * STRING_GAP)
+    };
+    let width =
+        (PADDING * 2.0 + neck_width).max(title.chars().count() as f32 * 9.0 + PADDING * 2.0);
+    let height = TITLE_HEIGHT + neck_height + PADDING;
+
+    let mut body = String::new();
+    let _ = writeln!(
+        body,
+        "<text x=\"{}\" y=\"18\" font-size=\"16\" font-weight=\"bold\" text-anchor=\"middle\">{}</text>",
+        num(width / 2.0),
+        escape(&title)
+    );
+
+    // Inlays first, so strings and dots are drawn over them
+    for fret in start..=end {
+        let along = f32::from(fret - start) + 0.5;
+        let across: &[f32] = match inlay(fret).map(|mark| mark.chars().count()) {
+            Some(1) => &[1.5],
+            Some(_) => &[0.5, 2.5],
+            None => &[],
+        };
+        for &string in across {
+            let (cx, cy) = frame.at(string, along);
+            let _ = writeln!(
+                body,
+                "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"3.5\" fill=\"#ccc\"/>"
+            );
+        }
+    }
+
+    // The neck: strings, fret wires and the nut, thick at fret 1
+    for string in 0..4 {
+        frame.line(&mut body, (string as f32, 0.0), (string as f32, span), 1.0);
+    }
+    for wire in 0..=(end - start + 1) {
+        let width = if wire == 0 && start == 1 { 4.0 } else { 1.0 };
+        frame.line(
+            &mut body,
+            (0.0, f32::from(wire)),
+            (3.0, f32::from(wire)),
+            width,
+        );
+    }
+
+    // Where the window starts
+    if vertical {
+        if start > 1 {
+            frame.text(
+                &mut body,
+                (3.6, 0.5),
+                12.0,
+                "start",
+                "",
+                &format!("{}fr", start),
+            );
+        }
+    } else {
+        for fret in start..=end {
+            let along = f32::from(fret - start) + 0.5;
+            frame.text(
+                &mut body,
+                (-0.9, along),
+                10.0,
+                "middle",
+                " fill=\"#555\"",
+                &fret.to_string(),
+            );
+        }
+        if start > 1 {
+            frame.text(
+                &mut body,
+                (-0.9, -1.0),
+                10.0,
+                "middle",
+                " fill=\"#555\"",
+                &format!("{}fr", start),
+            );
+        }
+    }
+
+    let names_at = if vertical { -1.3 } else { -1.5 };
+    for (drawn, i) in view.strings().into_iter().enumerate() {
+        let string = drawn as f32;
+        frame.text(
+            &mut body,
+            (string, names_at),
+            11.0,
+            "middle",
+            " fill=\"#333\"",
+            strings[i],
+        );
+        let (part, mark) = &marks[i];
+        match frets[i] {
+            None => {
+                // A small cross, as tall as it is wide in either orientation
+                let (at, dx) = (-0.55, 0.22);
+                let df = dx * STRING_GAP / FRET_GAP;
+                frame.line(
+                    &mut body,
+                    (string - dx, at - df),
+                    (string + dx, at + df),
+                    1.5,
+                );
+                frame.line(
+                    &mut body,
+                    (string - dx, at + df),
+                    (string + dx, at - df),
+                    1.5,
+                );
+            }
+            Some(0) if mark == "O" => {
+                let (cx, cy) = frame.at(string, -0.55);
+                let _ = writeln!(
+                    body,
+                    "<circle cx=\"{cx}\" cy=\"{cy}\" r=\"5\" fill=\"none\" stroke=\"black\" stroke-width=\"1.5\"/>"
+                );
+            }
+            Some(0) => frame.text(&mut body, (string, -0.55), 11.0, "middle", "", mark),
+            Some(fret) => {
+                let along = f32::from(fret.saturating_sub(start)) + 0.5;
+                let (cx, cy) = frame.at(string, along);
+                let _ = writeln!(
+                    body,
+                    "<circle class=\"dot {}\" cx=\"{cx}\" cy=\"{cy}\" r=\"{}\" fill=\"black\"/>",
+                    tone_class(*part),
+                    num(DOT_RADIUS)
+                );
+                if mark != "●" {
+                    let size = if mark.chars().count() > 2 { 8.0 } else { 10.0 };
+                    frame.text(
+                        &mut body,
+                        (string, along),
+                        size,
+                        "middle",
+                        " fill=\"white\" font-weight=\"bold\"",
+                        mark,
+                    );
+                }
+            }
+        }
+    }
+
+    Drawing {
+        width,
+        height,
+        body,
+    }
+}
+
+/// A CSS class naming a dot's role in the chord, for pages that colour them.
+fn tone_class(part: Part) -> &'static str {
+    match part {
+        Part::Dot(Tone::Root) => "root",
+        Part::Dot(Tone::Third) => "third",
+        Part::Dot(Tone::Fifth) => "fifth",
+        Part::Dot(Tone::Extension) => "extension",
+        _ => "note",
+    }
+}
+
+/// A coordinate rounded to a tenth, without trailing zeros.
+fn num(value: f32) -> String {
+    ((value * 10.0).round() / 10.0).to_string()
+}
+
+/// Text made safe to put inside an element.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chords::Chord;
+
+    fn c_major() -> Chord {
+        Chord::new("C".into(), [Some(0), Some(0), Some(0), Some(3)]).unwrap()
+    }
+
+    /// Every `<line>` as its x1 and x2 and stroke width.
+    fn lines(body: &str) -> Vec<(f32, f32, f32)> {
+        let attr = |line: &str, name: &str| -> f32 {
+            let at = line.find(&format!(" {name}=\"")).unwrap() + name.len() + 3;
+            let value = &line[at..];
+            value[..value.find('"').unwrap()].parse().unwrap()
+        };
+        body.lines()
+            .filter(|line| line.starts_with("<line"))
+            .map(|line| {
+                (
+                    attr(line, "x1"),
+                    attr(line, "x2"),
+                    attr(line, "stroke-width"),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nut_wire_at_fret_one_and_a_position_label_further_up() {
+        let c = c_major();
+        let open = drawing(&ChordDiagram::new(&c).window(1, 4)).body;
+        assert_eq!(lines(&open).iter().filter(|l| l.2 == 4.0).count(), 1);
+        assert!(!open.contains("fr</text>"));
+
+        let barre = Chord::new("D".into(), [Some(7), Some(6), Some(5), Some(5)]).unwrap();
+        for orientation in [Orientation::Horizontal, Orientation::Vertical] {
+            let diagram = ChordDiagram::new(&barre)
+                .window(5, 8)
+                .orientation(orientation);
+            let up = drawing(&diagram).body;
+            assert!(lines(&up).iter().all(|l| l.2 != 4.0), "{orientation:?}");
+            assert!(up.contains(">5fr</text>"), "{orientation:?}");
+        }
+    }
+
+    #[test]
+    fn mirrored_necks_put_the_nut_on_the_right() {
+        let c = c_major();
+        let nut_and_edges = |left_handed| {
+            let lines =
+                lines(&drawing(&ChordDiagram::new(&c).window(1, 4).left_handed(left_handed)).body);
+            let nut = lines.iter().find(|l| l.2 == 4.0).unwrap().0;
+            let xs = lines.iter().flat_map(|l| [l.0, l.1]);
+            let (min, max) = xs.fold((f32::MAX, f32::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)));
+            (nut, min, max)
+        };
+        let (nut, min, _) = nut_and_edges(false);
+        assert_eq!(nut, min);
+        let (nut, _, max) = nut_and_edges(true);
+        assert_eq!(nut, max);
+    }
+
+    #[test]
+    fn grid_wraps_past_max_width() {
+        let c = c_major();
+        let diagrams = vec![ChordDiagram::new(&c); 3];
+        let cell = drawing(&diagrams[0]);
+        let svg = grid(&diagrams, cell.width * 2.0 + SPACING);
+        let second = format!("translate({},0)", num(cell.width + SPACING));
+        let third = format!("translate(0,{})", num(cell.height + SPACING));
+        assert!(svg.contains("translate(0,0)"));
+        assert!(svg.contains(&second), "{svg}");
+        assert!(svg.contains(&third), "{svg}");
+
+        // Wide enough for all three, they share a row
+        let svg = grid(&diagrams, cell.width * 3.0 + SPACING * 2.0);
+        assert!(!svg.contains(&third));
+    }
+
+    #[test]
+    fn titles_are_escaped() {
+        let c = c_major();
+        let svg = chord(&ChordDiagram::new(&c).title("<C & \"friends\">"));
+        assert!(
+            svg.contains(">&lt;C &amp; &quot;friends&quot;&gt;</text>"),
+            "{svg}"
+        );
+        assert_eq!(escape("a<b&c\"d"), "a&lt;b&amp;c&quot;d");
+    }
+}
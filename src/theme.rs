@@ -0,0 +1,165 @@
+use std::env;
+
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::theory::Tone;
+use crate::widgets::Part;
+
+/// Names accepted for `theme` in the config file.
+pub const THEME_NAMES: [&str; 5] = ["dark", "light", "high-contrast", "colorblind", "mono"];
+
+/// Interface styles, picked by name in the config file.
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// Focused frames, overlays and the details pane
+    pub accent: Style,
+    /// Frames of unfocused diagrams
+    pub frame: Style,
+    /// Footer hints and status messages
+    pub hint: Style,
+    /// Diagram titles
+    pub title: Style,
+    /// String names and fret numbers
+    pub label: Style,
+    /// Empty frets and dividers
+    pub fretboard: Style,
+    pub open: Style,
+    pub muted: Style,
+    pub root: Style,
+    pub third: Style,
+    pub fifth: Style,
+    pub extension: Style,
+    /// Dots that aren't a known chord tone
+    pub note: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::named("dark").expect("built-in theme")
+    }
+}
+
+/// Plain foreground colour; `bold` adds the modifier.
+fn fg(color: Color) -> Style {
+    Style::default().fg(color)
+}
+
+fn bold(color: Color) -> Style {
+    fg(color).add_modifier(Modifier::BOLD)
+}
+
+impl Theme {
+    pub fn named(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme {
+                accent: fg(Color::Yellow),
+                frame: fg(Color::DarkGray),
+                hint: fg(Color::Gray),
+                title: Style::default().add_modifier(Modifier::BOLD),
+                label: fg(Color::Gray),
+                fretboard: fg(Color::DarkGray),
+                open: bold(Color::LightCyan),
+                muted: bold(Color::LightRed),
+                root: bold(Color::Yellow),
+                third: fg(Color::LightGreen),
+                fifth: fg(Color::LightBlue),
+                extension: fg(Color::LightMagenta),
+                note: Style::default(),
+            }),
+            "light" => Some(Theme {
+                accent: fg(Color::Blue),
+                frame: fg(Color::Gray),
+                hint: fg(Color::DarkGray),
+                title: Style::default().add_modifier(Modifier::BOLD),
+                label: fg(Color::DarkGray),
+                fretboard: fg(Color::Gray),
+                open: bold(Color::Cyan),
+                muted: bold(Color::Red),
+                root: bold(Color::Blue),
+                third: fg(Color::Green),
+                fifth: fg(Color::Magenta),
+                extension: fg(Color::Yellow),
+                note: Style::default(),
+            }),
+            // Bright colours, bold throughout, and the root in reverse video
+            "high-contrast" => Some(Theme {
+                accent: bold(Color::LightYellow),
+                frame: fg(Color::White),
+                hint: bold(Color::White),
+                title: bold(Color::White),
+                label: bold(Color::White),
+                fretboard: fg(Color::Gray),
+                open: bold(Color::LightCyan),
+                muted: bold(Color::LightRed),
+                root: bold(Color::LightYellow).add_modifier(Modifier::REVERSED),
+                third: bold(Color::LightGreen),
+                fifth: bold(Color::LightCyan),
+                extension: bold(Color::LightMagenta),
+                note: bold(Color::White),
+            }),
+            // The Okabe–Ito palette, distinguishable with the common colour
+            // vision deficiencies
+            "colorblind" | "colourblind" => Some(Theme {
+                accent: fg(Color::Rgb(230, 159, 0)),
+                frame: fg(Color::DarkGray),
+                hint: fg(Color::Gray),
+                title: Style::default().add_modifier(Modifier::BOLD),
+                label: fg(Color::Gray),
+                fretboard: fg(Color::DarkGray),
+                open: bold(Color::Rgb(86, 180, 233)),
+                muted: bold(Color::Rgb(213, 94, 0)),
+                root: bold(Color::Rgb(230, 159, 0)),
+                third: fg(Color::Rgb(86, 180, 233)),
+                fifth: fg(Color::Rgb(0, 158, 115)),
+                extension: fg(Color::Rgb(204, 121, 167)),
+                note: Style::default(),
+            }),
+            "mono" => Some(Theme::mono()),
+            _ => None,
+        }
+    }
+
+    /// No colours at all, only emphasis: what `NO_COLOR` asks for.
+    pub fn mono() -> Theme {
+        let plain = Style::default();
+        Theme {
+            accent: plain.add_modifier(Modifier::BOLD),
+            frame: plain,
+            hint: plain,
+            title: plain.add_modifier(Modifier::BOLD),
+            label: plain,
+            fretboard: plain.add_modifier(Modifier::DIM),
+            open: plain.add_modifier(Modifier::BOLD),
+            muted: plain.add_modifier(Modifier::BOLD),
+            root: plain.add_modifier(Modifier::REVERSED),
+            third: plain.add_modifier(Modifier::BOLD),
+            fifth: plain,
+            extension: plain.add_modifier(Modifier::UNDERLINED),
+            note: plain,
+        }
+    }
+
+    /// Whether the `NO_COLOR` environment variable is set to anything
+    /// (https://no-color.org).
+    pub fn no_color() -> bool {
+        env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+    }
+
+    /// Style for a piece of a diagram.
+    pub fn part(&self, part: Part) -> Style {
+        match part {
+            Part::Text => Style::default(),
+            Part::Title => self.title,
+            Part::Label => self.label,
+            Part::Open => self.open,
+            Part::Muted => self.muted,
+            Part::Fretboard => self.fretboard,
+            Part::Dot(Tone::Root) => self.root,
+            Part::Dot(Tone::Third) => self.third,
+            Part::Dot(Tone::Fifth) => self.fifth,
+            Part::Dot(Tone::Extension) => self.extension,
+            Part::Dot(Tone::Other) => self.note,
+            Part::Cursor => self.accent,
+        }
+    }
+}
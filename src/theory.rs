@@ -0,0 +1,434 @@
+use serde::Deserialize;
+
+/// Standard re-entrant ukulele tuning as MIDI note numbers: G4 C4 E4 A4.
+pub const STANDARD_TUNING: [u8; 4] = [67, 60, 64, 69];
+
+/// Note names used for display, indexed by pitch class.
+const SHARP_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+const FLAT_NAMES: [&str; 12] = [
+    "C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B",
+];
+
+/// How to spell black keys when there is no chord to spell them against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Accidentals {
+    #[default]
+    #[serde(alias = "sharp")]
+    Sharps,
+    #[serde(alias = "flat")]
+    Flats,
+}
+
+/// Recognised roots, longest first so "C#" wins over "C", with their pitch classes.
+pub const ROOTS: [(&str, u8); 17] = [
+    ("A#", 10),
+    ("Bb", 10),
+    ("C#", 1),
+    ("Db", 1),
+    ("D#", 3),
+    ("Eb", 3),
+    ("F#", 6),
+    ("Gb", 6),
+    ("G#", 8),
+    ("Ab", 8),
+    ("A", 9),
+    ("B", 11),
+    ("C", 0),
+    ("D", 2),
+    ("E", 4),
+    ("F", 5),
+    ("G", 7),
+];
+
+/// Chord qualities (as written after the root) and their formulas in scale degrees.
+pub const QUALITIES: [(&str, &[&str]); 26] = [
+    ("", &["1", "3", "5"]),
+    ("m", &["1", "b3", "5"]),
+    ("7", &["1", "3", "5", "b7"]),
+    ("maj7", &["1", "3", "5", "7"]),
+    ("m7", &["1", "b3", "5", "b7"]),
+    ("dim7", &["1", "b3", "b5", "bb7"]),
+    ("m7b5", &["1", "b3", "b5", "b7"]),
+    ("9", &["1", "3", "5", "b7", "9"]),
+    ("maj9", &["1", "3", "5", "7", "9"]),
+    ("m9", &["1", "b3", "5", "b7", "9"]),
+    ("6", &["1", "3", "5", "6"]),
+    ("m6", &["1", "b3", "5", "6"]),
+    ("add9", &["1", "3", "5", "9"]),
+    ("madd9", &["1", "b3", "5", "9"]),
+    ("sus2", &["1", "2", "5"]),
+    ("sus4", &["1", "4", "5"]),
+    ("7sus2", &["1", "2", "5", "b7"]),
+    ("7sus4", &["1", "4", "5", "b7"]),
+    ("7+5", &["1", "3", "#5", "b7"]),
+    ("7b5", &["1", "3", "b5", "b7"]),
+    ("mM7", &["1", "b3", "5", "7"]),
+    ("6/9", &["1", "3", "5", "6", "9"]),
+    ("aug", &["1", "3", "#5"]),
+    ("dim", &["1", "b3", "b5"]),
+    ("add11", &["1", "3", "5", "11"]),
+    ("madd11", &["1", "b3", "5", "11"]),
+];
+
+/// The part a note plays in its chord, for colour-coding diagrams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    Root,
+    Third,
+    Fifth,
+    /// Sixths, sevenths and beyond, and the 2 or 4 of a sus chord
+    Extension,
+    /// Not in the chord's formula, or the chord isn't known
+    Other,
+}
+
+impl Tone {
+    /// Classify a formula degree, e.g. "b3" → Third.
+    pub fn of_degree(degree: &str) -> Tone {
+        match degree.trim_start_matches(['b', '#']) {
+            "1" => Tone::Root,
+            "3" => Tone::Third,
+            "5" => Tone::Fifth,
+            _ => Tone::Extension,
+        }
+    }
+}
+
+/// A set of pitch classes, one bit per semitone (bit 0 = C).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PitchSet(pub u16);
+
+impl PitchSet {
+    /// Add a pitch class (taken modulo 12).
+    pub fn insert(&mut self, pc: u8) {
+        self.0 |= 1 << (pc % 12);
+    }
+
+    pub fn contains(&self, pc: u8) -> bool {
+        self.0 & (1 << (pc % 12)) != 0
+    }
+
+    pub fn len(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_subset(&self, other: PitchSet) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    /// Pitch classes in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..12).filter(|&pc| self.contains(pc))
+    }
+
+    /// Pitch classes present in `self` but not in `other`.
+    pub fn difference(&self, other: PitchSet) -> PitchSet {
+        PitchSet(self.0 & !other.0)
+    }
+}
+
+/// Split a chord name into its root and quality, e.g. "C#dim" → ("C#", "dim").
+pub fn split_root(name: &str) -> Option<(&str, &str)> {
+    ROOTS
+        .iter()
+        .find_map(|&(root, _)| Some((root, name.strip_prefix(root)?)))
+}
+
+/// Parse a note name such as "C", "f#", "Bb" or "Ebb" into a pitch class.
+pub fn parse_note(note: &str) -> Option<u8> {
+    let mut chars = note.chars();
+    let base: i32 = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let mut offset = 0;
+    for c in chars {
+        match c {
+            '#' | '♯' => offset += 1,
+            'b' | '♭' => offset -= 1,
+            _ => return None,
+        }
+    }
+    Some((base + offset).rem_euclid(12) as u8)
+}
+
+/// Generic interval names by semitone above the root.
+const INTERVAL_NAMES: [&str; 12] = [
+    "1", "b2", "2", "b3", "3", "4", "b5", "5", "#5", "6", "b7", "7",
+];
+
+/// Display name of a pitch class.
+pub fn note_name(pc: u8, accidentals: Accidentals) -> &'static str {
+    match accidentals {
+        Accidentals::Sharps => SHARP_NAMES[(pc % 12) as usize],
+        Accidentals::Flats => FLAT_NAMES[(pc % 12) as usize],
+    }
+}
+
+/// Parse a scientific pitch name like "G4" or "Bb3" into a MIDI note number.
+pub fn parse_pitch(s: &str) -> Option<u8> {
+    let s = s.trim();
+    let split = s.find(|c: char| c.is_ascii_digit() || c == '-')?;
+    let (note, octave) = s.split_at(split);
+    let octave: i32 = octave.parse().ok()?;
+    let midi = (octave + 1) * 12 + parse_note(note)? as i32;
+    u8::try_from(midi).ok().filter(|&m| m < 128)
+}
+
+/// Letter names for a tuning's strings, e.g. ["G", "C", "E", "A"].
+pub fn string_names(tuning: [u8; 4], accidentals: Accidentals) -> [&'static str; 4] {
+    tuning.map(|midi| note_name(midi, accidentals))
+}
+
+/// Scientific pitch name of a MIDI note spelled as `note`, so the octave
+/// follows the letter: (71, "Cb") → "Cb5", (60, "C") → "C4".
+pub fn spelled_pitch(note: &str, midi: u8) -> String {
+    let offset: i32 = note
+        .chars()
+        .skip(1)
+        .map(|c| if c == '#' { 1 } else { -1 })
+        .sum();
+    format!("{}{}", note, (midi as i32 - offset) / 12 - 1)
+}
+
+/// Interval label for a distance above the root, e.g. 10 → "b7".
+pub fn interval_name(semitones: u8) -> &'static str {
+    INTERVAL_NAMES[(semitones % 12) as usize]
+}
+
+/// Spell a scale degree above a named root with the right letter,
+/// e.g. ("Bb", "3") → "D" and ("F#", "b7") → "E".
+pub fn spell_degree(root: &str, degree: &str) -> Option<String> {
+    const LETTERS: [char; 7] = ['C', 'D', 'E', 'F', 'G', 'A', 'B'];
+    let root_pc = parse_note(root)?;
+    let letter_index = LETTERS.iter().position(|&l| root.starts_with(l))?;
+    let number: usize = degree.trim_start_matches(['b', '#']).parse().ok()?;
+    let letter = LETTERS[(letter_index + number.checked_sub(1)?) % 7];
+    let natural = parse_note(&letter.to_string())?;
+    let target = (root_pc + degree_semitones(degree)?) % 12;
+    let accidental = match (target as i32 - natural as i32).rem_euclid(12) {
+        0 => "",
+        1 => "#",
+        2 => "##",
+        10 => "bb",
+        11 => "b",
+        _ => return None,
+    };
+    Some(format!("{}{}", letter, accidental))
+}
+
+/// The root to spell a chord on `root` with `formula` from: `root` itself,
+/// unless that needs more double sharps or flats, or E#, B#, Cb and Fb, than
+/// its enharmonic twin does. A#7 would be A# C## E# G#, so it is spelled
+/// from Bb: Bb D F Ab.
+pub fn spelling_root<'a>(root: &'a str, formula: &[&str]) -> &'a str {
+    let Some(pc) = parse_note(root) else {
+        return root;
+    };
+    let twin = if root.contains('#') {
+        FLAT_NAMES[pc as usize]
+    } else if root.len() > 1 && root.ends_with('b') {
+        SHARP_NAMES[pc as usize]
+    } else {
+        return root;
+    };
+    let awkward = |root: &str| {
+        std::iter::once(Some(root.to_string()))
+            .chain(formula.iter().map(|d| spell_degree(root, d)))
+            .flatten()
+            .filter(|note| {
+                note.ends_with("##")
+                    || note.ends_with("bb")
+                    || matches!(note.as_str(), "E#" | "B#" | "Cb" | "Fb")
+            })
+            .count()
+    };
+    if awkward(twin) < awkward(root) {
+        twin
+    } else {
+        root
+    }
+}
+
+/// Name a sounding note in a chord on `root` with `formula`, and give its
+/// interval above the root: spelled against the formula's degree where one
+/// fits, e.g. ("Bb", 1 3 5, D4) → ("D", "3"), otherwise by `accidentals`.
+/// Pass the root through `spelling_root` first to avoid double accidentals.
+/// `None` if `root` isn't a note.
+pub fn spell_note(
+    root: &str,
+    formula: &[&str],
+    midi: u8,
+    accidentals: Accidentals,
+) -> Option<(String, String)> {
+    let semitones = (midi % 12 + 12 - parse_note(root)?) % 12;
+    let spelled = formula
+        .iter()
+        .find(|d| degree_semitones(d) == Some(semitones))
+        .and_then(|d| Some((spell_degree(root, d)?, d.to_string())));
+    Some(spelled.unwrap_or_else(|| {
+        (
+            note_name(midi, accidentals).to_string(),
+            interval_name(semitones).to_string(),
+        )
+    }))
+}
+
+/// Semitones above the root for a scale degree like "3", "b7" or "#11".
+pub fn degree_semitones(degree: &str) -> Option<u8> {
+    let digits = degree.trim_start_matches(['b', '#']);
+    let accidentals = &degree[..degree.len() - digits.len()];
+    let base: i32 = match digits.parse::<u8>().ok()? {
+        1 | 8 => 0,
+        2 | 9 => 2,
+        3 | 10 => 4,
+        4 | 11 => 5,
+        5 | 12 => 7,
+        6 | 13 => 9,
+        7 => 11,
+        _ => return None,
+    };
+    let offset: i32 = accidentals
+        .chars()
+        .map(|c| if c == '#' { 1 } else { -1 })
+        .sum();
+    Some((base + offset).rem_euclid(12) as u8)
+}
+
+/// Look up the formula for a chord quality, e.g. "m7" → ["1", "b3", "5", "b7"].
+pub fn quality_formula(quality: &str) -> Option<&'static [&'static str]> {
+    QUALITIES
+        .iter()
+        .find(|(q, _)| *q == quality)
+        .map(|(_, formula)| *formula)
+}
+
+/// The pitch classes of `root` + `quality`, if the quality is known.
+pub fn chord_pitch_set(root: u8, quality: &str) -> Option<PitchSet> {
+    let mut set = PitchSet::default();
+    for degree in quality_formula(quality)? {
+        set.insert(root + degree_semitones(degree)?);
+    }
+    Some(set)
+}
+
+/// Playable shapes for a pitch-class set under `tuning`, easiest first.
+///
+/// Every sounding string must belong to the set and all notes must be covered;
+/// for five-note chords the fifth (`fifth`, if given) may be left out.
+/// Shapes are searched up to fret 12 with a four-fret span; at most `limit` are returned.
+pub fn voicings(
+    set: PitchSet,
+    fifth: Option<u8>,
+    tuning: [u8; 4],
+    limit: usize,
+) -> Vec<[Option<u8>; 4]> {
+    let mut required = set;
+    if set.len() > 4
+        && let Some(pc) = fifth
+    {
+        required = PitchSet(set.0 & !(1 << (pc % 12)));
+    }
+    let mut found: Vec<(u8, [u8; 4])> = Vec::new();
+    for a in 0..=12u8 {
+        for b in 0..=12u8 {
+            for c in 0..=12u8 {
+                for d in 0..=12u8 {
+                    let frets = [a, b, c, d];
+                    let hi = frets.iter().copied().max().unwrap_or(0);
+                    let lo = frets.iter().copied().filter(|&f| f > 0).min().unwrap_or(0);
+                    if hi.saturating_sub(lo) > 3 {
+                        continue;
+                    }
+                    let mut sounding = PitchSet::default();
+                    for (string, &f) in frets.iter().enumerate() {
+                        sounding.insert(tuning[string] + f);
+                    }
+                    if !sounding.is_subset(set) || !required.is_subset(sounding) {
+                        continue;
+                    }
+                    found.push((hi * 4 + hi.saturating_sub(lo), frets));
+                }
+            }
+        }
+    }
+    found.sort();
+    found
+        .into_iter()
+        .take(limit)
+        .map(|(_, frets)| frets.map(Some))
+        .collect()
+}
+
+/// Name the chords a set of sounding MIDI notes could be, best guess first.
+///
+/// Exact formula matches come first, preferring a root in the bass; if none
+/// match, formulas that only lack their fifth are offered as "(no 5)".
+pub fn identify(pitches: &[u8], accidentals: Accidentals) -> Vec<String> {
+    let mut set = PitchSet::default();
+    for &p in pitches {
+        set.insert(p);
+    }
+    let bass = pitches.iter().min().map(|p| p % 12);
+    if set.len() < 2 {
+        return Vec::new();
+    }
+
+    let mut exact: Vec<(bool, usize, String)> = Vec::new();
+    let mut no_fifth: Vec<(bool, usize, String)> = Vec::new();
+    for root in 0..12u8 {
+        for (i, (quality, _)) in QUALITIES.iter().enumerate() {
+            let Some(chord) = chord_pitch_set(root, quality) else {
+                continue;
+            };
+            let name = format!("{}{}", note_name(root, accidentals), quality);
+            let key = (bass != Some(root), i, name);
+            if chord == set {
+                exact.push(key);
+            } else {
+                let mut without = chord;
+                without.0 &= !(1 << ((root + 7) % 12));
+                if without != chord && without == set {
+                    no_fifth.push((key.0, key.1, format!("{} (no 5)", key.2)));
+                }
+            }
+        }
+    }
+    exact.sort();
+    no_fifth.sort();
+    let pick = if exact.is_empty() { no_fifth } else { exact };
+    pick.into_iter().map(|(_, _, name)| name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_root_with_fewer_awkward_notes() {
+        let formula = |quality| quality_formula(quality).expect("known quality");
+        assert_eq!(spelling_root("A#", formula("7")), "Bb");
+        assert_eq!(spelling_root("D#", formula("")), "Eb");
+        assert_eq!(spelling_root("D#", formula("m")), "D#");
+        assert_eq!(spelling_root("Gb", formula("m")), "F#");
+        // Already clean, or no cleaner than the twin
+        assert_eq!(spelling_root("Bb", formula("7")), "Bb");
+        assert_eq!(spelling_root("F#", formula("")), "F#");
+        assert_eq!(spelling_root("Gb", formula("")), "Gb");
+        assert_eq!(spelling_root("C", formula("7")), "C");
+        assert_eq!(spelling_root("Bb", &[]), "Bb");
+    }
+}
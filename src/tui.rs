@@ -1,11 +1,16 @@
 use std::{
-    io,
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::PathBuf,
     time::{Duration, Instant},
 };
 
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind,
     },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
@@ -13,93 +18,1504 @@ use crossterm::{
 use ratatui::{
     Terminal,
     backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar,
+        ScrollbarOrientation, ScrollbarState,
+    },
 };
 use unicode_width::UnicodeWidthStr;
 
-use crate::chords::Chord;
+use uke_tui::chordpro::Song;
+use uke_tui::chords::{self, Chord};
+use uke_tui::theory::{self, Accidentals, PitchSet};
+use uke_tui::widgets::Diagram;
+
+use crate::builder::{Builder, Hit};
+use crate::complete::{self, Completer};
+use crate::config::Config;
+use crate::editor::LineEditor;
+use crate::export::{self, FORMAT_NAMES, Format, Sheet};
+use crate::grid::{GridCell, combine_diagrams_grid};
+use crate::keys::{Action, KeyBindings, Mode};
+use crate::library::{self, Library, Source};
+use crate::paths;
+use crate::suggest;
+use crate::watch::Watcher;
+
+/// Most voicings a chord panel cycles through.
+const MAX_VOICINGS: usize = 8;
+
+type Frets = [Option<u8>; 4];
+
+/// Shapes found for each set of notes (and optional fifth) searched so far.
+type VoicingCache = HashMap<(PitchSet, Option<u8>), Vec<Frets>>;
+
+/// Incremental search in the help overlay.
+struct HelpSearch {
+    query: LineEditor,
+    /// Whether keys are still going to the query
+    typing: bool,
+    /// Help row of the current match
+    current: Option<usize>,
+    /// Move to a match at the next draw: 0 = the first from the current
+    /// one, 1 = the next, -1 = the previous
+    jump: Option<isize>,
+}
+
+/// What running a bound action did with the key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Done,
+    /// The action doesn't apply right now; the key's next binding gets a turn
+    Pass,
+    Quit,
+}
+
+/// One box in the diagram grid.
+enum Panel {
+    /// A chord found for an entry, with the voicings it can cycle through
+    Chord {
+        key: String,
+        voicings: Vec<Chord>,
+        voicing: usize,
+    },
+    /// An entry with no matching chord, plus "did you mean" names
+    Missing {
+        key: String,
+        suggestions: Vec<String>,
+    },
+    /// Prompts and errors
+    Message(String),
+}
+
+impl Panel {
+    /// The voicing currently shown, for chord panels.
+    fn chord(&self) -> Option<&Chord> {
+        match self {
+            Panel::Chord {
+                voicings, voicing, ..
+            } => voicings.get(*voicing),
+            _ => None,
+        }
+    }
+
+    /// The text the user typed for this panel, if it came from an entry.
+    fn key(&self) -> Option<&str> {
+        match self {
+            Panel::Chord { key, .. } | Panel::Missing { key, .. } => Some(key),
+            Panel::Message(_) => None,
+        }
+    }
+}
 
 /// Struct for the TUI app.
 pub struct App {
-    input: String,
-    chords: Vec<Chord>,
-    diagrams: Vec<String>,
-    scroll: u16,      // scroll for diagrams
-    help_shown: bool, // whether help modal is visible
-    help_scroll: u16, // scroll for help modal
+    input: LineEditor,
+    library: Library,
+    panels: Vec<Panel>,
+    scroll: u16,                     // scroll for diagrams
+    help_shown: bool,                // whether help modal is visible
+    help_scroll: u16,                // scroll for help modal
+    help_view: u16,                  // help lines visible at the last draw
+    help_search: Option<HelpSearch>, // search within the help, when started
+    diagram_rows: u16,               // height of the laid-out diagram grid
+    diagram_view: u16,               // rows visible in the diagram pane
+    completer: Completer,
+    completions: Vec<String>, // popup entries for the chord being typed
+    completion_selected: Option<usize>, // highlighted popup entry, once navigated
+    focus: Option<usize>,     // focused chord panel
+    grid_active: bool,        // keys go to the diagram grid instead of the input
+    grid_cells: Vec<GridCell>, // panel positions from the last draw
+    details_shown: bool,      // whether the focused chord's details are open
+    status: Option<String>,   // one-off message shown in the footer
+    clipboard: Option<String>, // text waiting to be sent to the terminal clipboard
+    builder: Option<Builder>, // chord builder, when open
+    builder_origin: (u16, u16), // screen position of the builder's fretboard
+    config: Config,           // settings from the config file
+    watcher: Watcher,         // library and song files to reload when they change
+    song: Option<PathBuf>,    // ChordPro file whose chords are shown
+    lyrics: Option<Song>,     // the open song as last read, while its chords are in the grid
+    problems: Vec<String>,    // what the last reload couldn't read, until fixed
+    voicing_cache: RefCell<VoicingCache>, // shapes found per set of notes
 }
 
 impl App {
-    /// Creates a new app instance
-    pub fn new(chords: Vec<Chord>) -> Self {
+    /// Creates a new app instance; `status` is shown in the footer until the first key press.
+    pub fn new(library: Library, config: Config, status: Option<String>) -> Self {
         Self {
-            input: String::new(),
-            chords,
-            diagrams: vec!["Type comma separated chords and press Enter.".into()],
+            input: LineEditor::with_history(),
+            completer: Completer::new(&library.chords),
+            completions: Vec::new(),
+            completion_selected: None,
+            watcher: Watcher::new(&library.paths()),
+            problems: library.problems(),
+            library,
+            panels: vec![Panel::Message(format!(
+                "Type comma separated chords and press {}.",
+                config.keys.key(Action::Lookup)
+            ))],
             scroll: 0,
             help_shown: false,
             help_scroll: 0,
+            help_view: 0,
+            help_search: None,
+            diagram_rows: 0,
+            diagram_view: 0,
+            focus: None,
+            grid_active: false,
+            grid_cells: Vec::new(),
+            details_shown: false,
+            status,
+            clipboard: None,
+            builder: None,
+            builder_origin: (0, 0),
+            config,
+            song: None,
+            lyrics: None,
+            voicing_cache: RefCell::default(),
+        }
+    }
+
+    /// Show the chords of a ChordPro song, and reload them whenever it
+    /// changes on disk.
+    pub fn open_song(&mut self, path: PathBuf) {
+        self.song = Some(path);
+        self.watcher = self.watch();
+        // Startup warnings win over the song's summary
+        let status = self.status.take();
+        self.load_song();
+        self.status = status.or(self.status.take());
+        self.problems = self.reload_problems();
+    }
+
+    /// Look up the open song's chords, in order of first appearance. Slash
+    /// chords fall back to their upper chord; missing ones get suggestions.
+    fn load_song(&mut self) {
+        let Some(path) = &self.song else {
+            return;
+        };
+        let Ok(text) = fs::read_to_string(path) else {
+            return;
+        };
+        let (mut sheet, missing) = export::song(&text, &self.library);
+        if sheet.song.title.is_none() {
+            sheet.song.title = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+        }
+        let title = sheet.song.title.clone();
+        self.lyrics = Some(sheet.song);
+        let panels: Vec<Panel> = sheet
+            .chords
+            .into_iter()
+            .map(|(name, chord)| self.chord_panel(name, chord))
+            .chain(missing.into_iter().map(|key| {
+                let suggestions = suggest::suggest(&key, &self.library.chords, 3);
+                Panel::Missing { key, suggestions }
+            }))
+            .collect();
+        self.panels = if panels.is_empty() {
+            vec![Panel::Message(format!(
+                "No chords in {}",
+                paths::display(path)
+            ))]
+        } else {
+            panels
+        };
+        self.scroll = 0;
+        self.focus = self.panels.iter().position(|p| p.chord().is_some());
+        self.details_shown = false;
+        self.status = Some(format!(
+            "{}: {} chords",
+            title.unwrap_or_else(|| paths::display(path)),
+            self.panels.len()
+        ));
+    }
+
+    /// Library problems, plus the open song if it can't be read.
+    fn reload_problems(&self) -> Vec<String> {
+        let mut problems = self.library.problems();
+        if let Some(path) = &self.song
+            && let Err(e) = fs::read_to_string(path)
+        {
+            problems.push(format!("{}: {}", paths::display(path), e));
         }
+        problems
+    }
+
+    /// A watcher for every library file and the open song.
+    fn watch(&self) -> Watcher {
+        let mut files = self.library.paths();
+        files.extend(self.song.clone());
+        Watcher::new(&files)
     }
 
     /// Chord lookup with help funtionality
     fn lookup(&mut self) {
         self.help_shown = false;
         self.help_scroll = 0;
-        let raw = self.input.trim();
-        self.diagrams.clear();
+        let raw = self.input.text().trim().to_string();
+        self.input.push_history(&raw);
+        if let Some(command) = raw.strip_prefix(':') {
+            self.run_command(command);
+            self.input.clear();
+            self.close_completions();
+            return;
+        }
+        self.panels.clear();
+        self.lyrics = None;
         if raw.is_empty() {
-            self.diagrams
-                .push("Please enter one or more chords, separated by commas.".into());
+            self.panels.push(Panel::Message(
+                "Please enter one or more chords, separated by commas.".into(),
+            ));
+        } else if let Some(notes) = strip_prefix_ignore_case(&raw, "notes:") {
+            self.lookup_notes(notes);
         } else {
             // collect matches / not-founds
-            let mut selected = Vec::new();
+            let picks = self.config.keys.bound(Action::PickSuggestion).count();
+            let mut offered = 0;
             for entry in raw.split(',') {
                 let key = entry.trim().to_string();
                 if key.is_empty() {
                     continue;
                 }
-                match self.chords.iter().find(|c| c.matches_name(&key)) {
-                    Some(ch) => selected.push((key, ch)),
-                    None => self.diagrams.push(format!("Chord not found: {}", key)),
+                let panel = match self.library.chords.iter().find(|c| c.matches_name(&key)) {
+                    Some(ch) => self.chord_panel(key, ch.clone()),
+                    None => {
+                        // Suggestions are numbered across all unknown entries,
+                        // one for each key that picks them
+                        let room = picks.saturating_sub(offered);
+                        let suggestions = suggest::suggest(&key, &self.library.chords, room.min(3));
+                        offered += suggestions.len();
+                        Panel::Missing { key, suggestions }
+                    }
+                };
+                self.panels.push(panel);
+            }
+        }
+        self.input.clear();
+        self.close_completions();
+        self.scroll = 0;
+        self.focus = self.panels.iter().position(|p| p.chord().is_some());
+        self.details_shown = false;
+    }
+
+    /// Run a `:command` typed into the input box.
+    fn run_command(&mut self, command: &str) {
+        let (name, arg) = command
+            .trim()
+            .split_once(' ')
+            .map_or((command.trim(), ""), |(n, a)| (n, a.trim()));
+        match name {
+            "build" => {
+                let start = self.library.chords.iter().find(|c| c.matches_name(arg));
+                if start.is_none() && !arg.is_empty() {
+                    self.status = Some(format!("Chord not found: {}", arg));
                 }
+                self.builder = Some(Builder::new(start.map(|c| c.frets), &self.config));
+                self.leave_grid();
             }
-            if !selected.is_empty() {
-                let mut gmin = u8::MAX;
-                let mut gmax = 0u8;
-                let mut has_open = false;
-                for (_, chord) in &selected {
-                    if chord.frets.iter().any(|&f| f == Some(0)) {
-                        has_open = true;
-                    }
-                    if let Some((mn, mx)) = chord.fret_bounds() {
-                        gmin = gmin.min(mn);
-                        gmax = gmax.max(mx);
+            "song" if !arg.is_empty() => self.open_song(paths::expand_home(arg)),
+            "sources" => {
+                self.panels = self.library.layers.iter().map(layer_panel).collect();
+                self.scroll = 0;
+                self.focus = None;
+                self.details_shown = false;
+            }
+            "export" => self.export(arg),
+            _ => self.status = Some(format!("Unknown command: :{}", name)),
+        }
+    }
+
+    /// `:export FORMAT [FILE]`: write the chords in the grid, as shown, to
+    /// FILE or to `chords-sheet.FORMAT` in the current directory.
+    fn export(&mut self, arg: &str) {
+        let (name, path) = arg
+            .split_once(' ')
+            .map_or((arg, ""), |(n, p)| (n, p.trim()));
+        let Some(format) = Format::named(name) else {
+            self.status = Some(format!("Export as one of: {}", FORMAT_NAMES.join(", ")));
+            return;
+        };
+        let sheet = self.sheet();
+        if sheet.chords.is_empty() {
+            self.status = Some("No chords to export".into());
+            return;
+        }
+        let path = if path.is_empty() {
+            PathBuf::from(format.default_file())
+        } else {
+            PathBuf::from(path)
+        };
+        if self.library.holds(&path) {
+            self.status = Some(format!(
+                "Won't overwrite the chord library {}",
+                path.display()
+            ));
+            return;
+        }
+        self.status = Some(match export::write(format, &sheet, &self.config, &path) {
+            Ok(()) => format!(
+                "Exported {} chords to {}",
+                sheet.chords.len(),
+                path.display()
+            ),
+            Err(e) => format!("Couldn't write {}: {}", path.display(), e),
+        });
+    }
+
+    /// The chords in the grid, with the voicings picked: what `:export`
+    /// writes. The open song's lyrics, key and capo come along while its
+    /// chords are shown; a typed progression is titled with their names.
+    fn sheet(&self) -> Sheet {
+        let chords: Vec<(String, Chord)> = self
+            .panels
+            .iter()
+            .filter_map(|panel| match panel {
+                Panel::Chord { key, .. } => Some((
+                    key.split(' ').next().unwrap_or(key).to_string(),
+                    panel.chord()?.clone(),
+                )),
+                _ => None,
+            })
+            .collect();
+        let song = self.lyrics.clone().unwrap_or_else(|| {
+            let names: Vec<&str> = chords.iter().map(|(name, _)| name.as_str()).collect();
+            Song::titled(names.join(", "))
+        });
+        Sheet { song, chords }
+    }
+
+    /// Handle a key press; returns false to quit. The key's bindings in the
+    /// current mode are tried in order, then the global ones, and in the input
+    /// box anything left over edits the text.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        self.status = None;
+        let mode = if self.help_shown {
+            Mode::Help
+        } else if self.builder.is_some() {
+            Mode::Builder
+        } else if self.grid_active {
+            Mode::Grid
+        } else {
+            Mode::Input
+        };
+        let prompt = self.config.keys.actions(Mode::Prompt, &key).next();
+        // Typing a help search
+        if let Some(search) = self.help_search.as_mut()
+            && search.typing
+            && self.help_shown
+        {
+            match prompt {
+                Some(Action::Confirm) => search.typing = false,
+                Some(Action::Dismiss) => self.help_search = None,
+                _ if edit_input(&mut search.query, &self.config.keys, &key) => {
+                    search.jump = Some(0);
+                }
+                _ => {}
+            }
+            return true;
+        }
+        // The builder's save prompt is a small line editor of its own
+        if let Some(builder) = self.builder.as_mut()
+            && let Some(naming) = builder.naming.as_mut()
+            && !self.help_shown
+        {
+            match prompt {
+                Some(Action::Confirm) => {
+                    let name = naming.text().trim().to_string();
+                    self.save_builder(name);
+                }
+                Some(Action::Dismiss) => builder.naming = None,
+                _ => {
+                    edit_input(naming, &self.config.keys, &key);
+                }
+            }
+            return true;
+        }
+        let keys = &self.config.keys;
+        let actions: Vec<Action> = keys
+            .actions(mode, &key)
+            .chain(keys.actions(Mode::Global, &key))
+            .collect();
+        for action in actions {
+            let outcome = match (mode, action) {
+                (_, Action::Help) => {
+                    self.help_shown = !self.help_shown;
+                    self.help_scroll = 0;
+                    self.help_search = None;
+                    Outcome::Done
+                }
+                // Quitting from the help just closes it
+                (Mode::Help, Action::Quit) => {
+                    self.help_shown = false;
+                    Outcome::Done
+                }
+                (_, Action::Quit) => Outcome::Quit,
+                (Mode::Input, _) => self.input_action(action, &key),
+                (Mode::Grid, _) => self.grid_action(action),
+                (Mode::Builder, _) => self.builder_action(action),
+                (Mode::Help, _) => self.help_action(action),
+                _ => Outcome::Pass,
+            };
+            match outcome {
+                Outcome::Done => return true,
+                Outcome::Quit => return false,
+                Outcome::Pass => {}
+            }
+        }
+        if mode == Mode::Input && edit_input(&mut self.input, &self.config.keys, &key) {
+            self.update_completions();
+        }
+        true
+    }
+
+    /// Run an input box action.
+    fn input_action(&mut self, action: Action, key: &KeyEvent) -> Outcome {
+        // Unmodified Home/End scroll only when there's no text to move through
+        let scroll_keys = self.input.is_empty() || key.modifiers.contains(KeyModifiers::CONTROL);
+        match action {
+            Action::AcceptCompletion if self.completion_selected.is_some() => {
+                self.accept_completion()
+            }
+            Action::Lookup => self.lookup(),
+            // "Did you mean" suggestions are picked from an empty input, the
+            // nth key bound picking the nth suggestion
+            Action::PickSuggestion if self.input.is_empty() => {
+                let n = self.config.keys.bound(action).position(|k| k.matches(key));
+                if let Some(n) = n {
+                    self.pick_suggestion(n);
+                }
+            }
+            Action::NextCompletion if !self.completions.is_empty() => self.cycle_completion(1),
+            Action::PrevCompletion if !self.completions.is_empty() => self.cycle_completion(-1),
+            Action::Select => self.enter_grid(),
+            Action::HistoryPrev => {
+                self.input.history_prev();
+                self.close_completions();
+            }
+            Action::HistoryNext => {
+                self.input.history_next();
+                self.close_completions();
+            }
+            Action::ScrollUp => self.scroll_by(-1),
+            Action::ScrollDown => self.scroll_by(1),
+            Action::PageUp => self.scroll_by(-(self.diagram_view.max(1) as i32)),
+            Action::PageDown => self.scroll_by(self.diagram_view.max(1) as i32),
+            Action::ScrollTop if scroll_keys => self.scroll = 0,
+            Action::ScrollBottom if scroll_keys => self.scroll = self.max_scroll(),
+            Action::CloseCompletions if !self.completions.is_empty() => self.close_completions(),
+            Action::Cancel => return Outcome::Quit,
+            _ => return Outcome::Pass,
+        }
+        Outcome::Done
+    }
+
+    /// Run a diagram grid action on the focused chord.
+    fn grid_action(&mut self, action: Action) -> Outcome {
+        match action {
+            Action::CloseDetails if self.details_shown => self.details_shown = false,
+            Action::LeaveGrid => self.leave_grid(),
+            Action::FocusLeft => self.focus_step(-1),
+            Action::FocusRight => self.focus_step(1),
+            Action::FocusUp => self.focus_row(-1),
+            Action::FocusDown => self.focus_row(1),
+            Action::Voicing => self.cycle_voicing(1),
+            Action::PrevVoicing => self.cycle_voicing(-1),
+            Action::Details => self.details_shown = !self.details_shown,
+            Action::Remove => self.remove_focused(),
+            Action::MoveLeft => self.move_focused(-1),
+            Action::MoveRight => self.move_focused(1),
+            Action::Copy => self.copy_focused(),
+            Action::Edit => self.edit_focused(),
+            Action::Turn => self.config.orientation = self.config.orientation.turned(),
+            Action::Labels => self.config.labels = self.config.labels.next(),
+            _ => return Outcome::Pass,
+        }
+        Outcome::Done
+    }
+
+    /// Run a chord builder action.
+    fn builder_action(&mut self, action: Action) -> Outcome {
+        let Some(builder) = self.builder.as_mut() else {
+            return Outcome::Pass;
+        };
+        // The status was cleared by this key press, so these only ever set it
+        match action {
+            Action::FretLeft => builder.move_along(-1),
+            Action::FretRight => builder.move_along(1),
+            Action::StringUp => builder.move_cursor(-1, 0),
+            Action::StringDown => builder.move_cursor(1, 0),
+            Action::ToNut => builder.move_cursor(0, -(chords::NECK_FRETS as isize)),
+            Action::ToLastFret => builder.move_cursor(0, chords::NECK_FRETS as isize),
+            Action::Toggle => builder.toggle_at_cursor(),
+            Action::Mute => builder.mute_at_cursor(),
+            Action::SlideUp => self.status = builder.slide(1).err(),
+            Action::SlideDown => self.status = builder.slide(-1).err(),
+            Action::Undo => self.status = (!builder.undo()).then(|| "Nothing to undo".into()),
+            Action::Redo => self.status = (!builder.redo()).then(|| "Nothing to redo".into()),
+            Action::Clear => builder.clear(),
+            Action::Save => {
+                let mut naming = LineEditor::default();
+                if let Some(name) = builder.default_name(&self.library.chords) {
+                    naming.set_text(name);
+                }
+                builder.naming = Some(naming);
+            }
+            Action::Show => self.show_builder(),
+            Action::Close => self.builder = None,
+            _ => return Outcome::Pass,
+        }
+        Outcome::Done
+    }
+
+    /// Run a help overlay action.
+    fn help_action(&mut self, action: Action) -> Outcome {
+        let page = self.help_view.max(1);
+        match action {
+            Action::CloseHelp => self.help_shown = false,
+            Action::HelpUp => self.help_scroll = self.help_scroll.saturating_sub(1),
+            Action::HelpDown => self.help_scroll = self.help_scroll.saturating_add(1),
+            Action::HelpPageUp => self.help_scroll = self.help_scroll.saturating_sub(page),
+            Action::HelpPageDown => self.help_scroll = self.help_scroll.saturating_add(page),
+            Action::HelpSearch => {
+                self.help_search = Some(HelpSearch {
+                    query: LineEditor::default(),
+                    typing: true,
+                    current: None,
+                    jump: None,
+                })
+            }
+            Action::HelpNextMatch | Action::HelpPrevMatch if self.help_search.is_some() => {
+                let step = if action == Action::HelpNextMatch {
+                    1
+                } else {
+                    -1
+                };
+                if let Some(search) = self.help_search.as_mut() {
+                    search.jump = Some(step);
+                }
+            }
+            _ => return Outcome::Pass,
+        }
+        Outcome::Done
+    }
+
+    /// Toggle whatever was clicked on the builder's fretboard.
+    fn builder_click(&mut self, column: u16, row: u16) {
+        let (x0, y0) = self.builder_origin;
+        let Some(builder) = self.builder.as_mut() else {
+            return;
+        };
+        if column < x0 || row < y0 || builder.naming.is_some() {
+            return;
+        }
+        if let Some(hit) = builder.hit(column - x0, row - y0) {
+            builder.point_at(&hit);
+            match hit {
+                Hit::Nut(string) => builder.toggle_nut(string),
+                Hit::Fret(string, fret) => builder.toggle_fret(string, fret),
+            }
+        }
+    }
+
+    /// Save the builder's shape under `name`, replacing any library entry of
+    /// that name in place.
+    fn save_builder(&mut self, name: String) {
+        let Some(builder) = self.builder.as_mut() else {
+            return;
+        };
+        if let Err(e) = builder.validate() {
+            self.status = Some(e);
+            return;
+        }
+        let Some(chord) = Chord::new(name.clone(), builder.frets()) else {
+            self.status = Some(format!("Not a chord name: {}", name));
+            return;
+        };
+        builder.naming = None;
+        builder.source = Some(chord.name.clone());
+        let path = self.config.save_to.clone();
+        match chord.save_to_library(&path) {
+            Ok(replaced) => {
+                let verb = if replaced { "Replaced" } else { "Added" };
+                let mut status =
+                    format!("{} {} in {}", verb, chord.to_line(), paths::display(&path));
+                self.reload_library();
+                // A later layer, such as a project library, may define it too
+                let winner = self
+                    .library
+                    .chords
+                    .iter()
+                    .find(|c| c.matches_name(&chord.name))
+                    .and_then(|c| self.library.source_of(c));
+                if let Some(Source::File(winner)) = winner
+                    && !library::same_file(winner, &path)
+                {
+                    status.push_str(&format!(" (overridden by {})", paths::display(winner)));
+                }
+                self.status = Some(status);
+            }
+            Err(e) => self.status = Some(format!("Could not save {}: {}", name, e)),
+        }
+    }
+
+    /// Read every library layer again, e.g. after saving a chord.
+    fn reload_library(&mut self) {
+        self.library = Library::load(&self.library.paths());
+        self.completer = Completer::new(&self.library.chords);
+        // Our own saves shouldn't trigger another reload
+        self.watcher = self.watch();
+    }
+
+    /// Periodic work from the event loop: pick up edits to library files and
+    /// the open song. Problems stay in the footer until a reload fixes them.
+    fn on_tick(&mut self) {
+        let changed = self.watcher.changed();
+        if changed.is_empty() {
+            return;
+        }
+        let song_changed = self
+            .song
+            .as_ref()
+            .is_some_and(|song| changed.contains(song));
+        if changed.len() > usize::from(song_changed) {
+            self.reload_library();
+            self.refresh_panels();
+            self.status = Some(format!(
+                "Reloaded the library ({} chords)",
+                self.library.chords.len()
+            ));
+        }
+        if song_changed {
+            self.load_song();
+        }
+        self.problems = self.reload_problems();
+        if !self.problems.is_empty() {
+            self.status = None;
+        }
+    }
+
+    /// Look the shown chords up again after the library changed, keeping
+    /// each panel's voicing where it still exists. Shapes that aren't in the
+    /// library (from the builder or a notes search) stay as they are.
+    fn refresh_panels(&mut self) {
+        let panels = std::mem::take(&mut self.panels);
+        self.panels = panels
+            .into_iter()
+            .map(|panel| {
+                let (key, shown) = match &panel {
+                    Panel::Chord { key, .. } => (key, panel.chord().map(|c| c.name.as_str())),
+                    Panel::Missing { key, .. } => (key, None),
+                    Panel::Message(_) => return panel,
+                };
+                let name = shown.unwrap_or(key);
+                let Some(found) = self.library.chords.iter().find(|c| c.matches_name(name)) else {
+                    return panel;
+                };
+                let mut fresh = self.chord_panel(key.clone(), found.clone());
+                // A panel on its first voicing follows the library entry
+                if let (
+                    Panel::Chord {
+                        voicings: old,
+                        voicing: old_voicing @ 1..,
+                        ..
+                    },
+                    Panel::Chord {
+                        voicings, voicing, ..
+                    },
+                ) = (&panel, &mut fresh)
+                    && let Some(i) = voicings
+                        .iter()
+                        .position(|v| v.frets == old[*old_voicing].frets)
+                {
+                    *voicing = i;
+                }
+                fresh
+            })
+            .collect();
+    }
+
+    /// Close the builder and show its shape in the diagram grid.
+    fn show_builder(&mut self) {
+        let Some(builder) = &self.builder else {
+            return;
+        };
+        let chord = builder
+            .default_name(&self.library.chords)
+            .and_then(|n| Chord::new(n, builder.frets()));
+        match chord {
+            Some(chord) => {
+                let panel = self.chord_panel(chord.name.clone(), chord);
+                self.panels = vec![panel];
+                self.focus = Some(0);
+                self.scroll = 0;
+                self.builder = None;
+            }
+            None => self.status = Some("This shape has no name; save it with s".into()),
+        }
+    }
+
+    /// A chord panel showing `chord` first, then its other voicings.
+    fn chord_panel(&self, key: String, chord: Chord) -> Panel {
+        let mut voicings = vec![chord];
+        let first = &voicings[0];
+        // Other library shapes under the same name
+        let library: Vec<Chord> = self
+            .library
+            .chords
+            .iter()
+            .filter(|c| c.name == first.name && c.frets != first.frets)
+            .cloned()
+            .collect();
+        // Then shapes worked out from the chord's formula
+        let mut derived = Vec::new();
+        if let Some((root, quality)) = first.root_and_quality()
+            && let Some(set) = theory::chord_pitch_set(root, &quality)
+        {
+            for frets in self.voicings(set, Some(root + 7)) {
+                if let Some(c) = Chord::new(first.name.clone(), frets) {
+                    derived.push(c);
+                }
+            }
+        }
+        for c in library.into_iter().chain(derived) {
+            if voicings.len() < MAX_VOICINGS && !voicings.iter().any(|v| v.frets == c.frets) {
+                voicings.push(c);
+            }
+        }
+        Panel::Chord {
+            key,
+            voicings,
+            voicing: 0,
+        }
+    }
+
+    /// Shapes for a set of notes, easiest first. The search tries every
+    /// shape on the neck, so each set is searched once and the result kept.
+    fn voicings(&self, set: PitchSet, fifth: Option<u8>) -> Vec<Frets> {
+        self.voicing_cache
+            .borrow_mut()
+            .entry((set, fifth))
+            .or_insert_with(|| theory::voicings(set, fifth, self.config.tuning, MAX_VOICINGS))
+            .clone()
+    }
+
+    /// Swap suggestion `n`, counting from 0, into the last lookup and run it
+    /// again.
+    fn pick_suggestion(&mut self, n: usize) {
+        let picked = self
+            .panels
+            .iter()
+            .enumerate()
+            .flat_map(|(i, p)| match p {
+                Panel::Missing { suggestions, .. } => suggestions.iter().map(|s| (i, s)).collect(),
+                _ => Vec::new(),
+            })
+            .nth(n);
+        let Some((index, name)) = picked else {
+            return;
+        };
+        let entries: Vec<&str> = self
+            .panels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| {
+                if i == index {
+                    Some(name.as_str())
+                } else {
+                    p.key()
+                }
+            })
+            .collect();
+        self.input.set_text(entries.join(", "));
+        self.lookup();
+    }
+
+    /// Every panel rendered as a diagram, chords sharing one fret window.
+    fn diagrams(&self) -> Vec<Diagram> {
+        let window = chords::fret_window(
+            self.panels.iter().filter_map(Panel::chord),
+            self.config.fret_window,
+        );
+        // Each suggestion is labelled with the key that picks it
+        let mut picks = self.config.keys.bound(Action::PickSuggestion);
+        self.panels
+            .iter()
+            .map(|panel| match panel {
+                Panel::Chord { key, .. } => {
+                    let chord = panel.chord().expect("chord panel has a voicing");
+                    export::diagram(key, chord, window, &self.config)
+                        .title(format!("Chord: {}", key))
+                        .diagram()
+                }
+                Panel::Missing { key, suggestions } => {
+                    let mut msg = format!("Chord not found: {}", key);
+                    if !suggestions.is_empty() {
+                        msg.push_str("\nDid you mean:");
+                        for name in suggestions {
+                            let key = picks.next().map(ToString::to_string);
+                            msg.push_str(&format!("\n [{}] {}", key.unwrap_or_default(), name));
+                        }
                     }
+                    Diagram::plain(&msg)
+                }
+                Panel::Message(msg) => Diagram::plain(msg),
+            })
+            .collect()
+    }
+
+    /// The help's overview of the loaded library, `width` columns wide: how
+    /// many chords there are of each root and quality, and which root and
+    /// quality combinations have no chord.
+    fn library_help(&self, width: usize) -> Vec<String> {
+        let accidentals = self.config.accidentals;
+        let mut by_root = [0usize; 12];
+        let mut by_quality: Vec<(String, usize)> = Vec::new();
+        let mut present = HashSet::new();
+        for chord in &self.library.chords {
+            let Some((root, quality)) = chord.root_and_quality() else {
+                continue;
+            };
+            by_root[root as usize] += 1;
+            match by_quality.iter_mut().find(|(q, _)| *q == quality) {
+                Some((_, n)) => *n += 1,
+                None => by_quality.push((quality.clone(), 1)),
+            }
+            present.insert((root, quality));
+        }
+        // Known qualities in the usual order, then any others the library adds
+        by_quality.sort_by_key(|(q, _)| {
+            theory::QUALITIES
+                .iter()
+                .position(|(known, _)| known == q)
+                .unwrap_or(theory::QUALITIES.len())
+        });
+        let quality_name = |q: &str| if q.is_empty() { "major" } else { q }.to_string();
+
+        let roots: Vec<String> = (0..12u8)
+            .map(|pc| {
+                format!(
+                    "{} {}",
+                    theory::note_name(pc, accidentals),
+                    by_root[pc as usize]
+                )
+            })
+            .collect();
+        let qualities: Vec<String> = by_quality
+            .iter()
+            .map(|(q, n)| format!("{} {}", quality_name(q), n))
+            .collect();
+        let missing: Vec<String> = by_quality
+            .iter()
+            .filter_map(|(q, _)| {
+                let roots: Vec<&str> = (0..12u8)
+                    .filter(|&pc| !present.contains(&(pc, q.clone())))
+                    .map(|pc| theory::note_name(pc, accidentals))
+                    .collect();
+                (!roots.is_empty()).then(|| format!("{}: {}", quality_name(q), roots.join(" ")))
+            })
+            .collect();
+
+        let mut lines = vec![format!(
+            "Library: {} chords, {} qualities (:sources for the files)",
+            self.library.chords.len(),
+            by_quality.len()
+        )];
+        lines.push("  By root".into());
+        lines.extend(fill(&roots, 4, width));
+        lines.push("  By quality (\"major\" is written without a suffix)".into());
+        lines.extend(fill(&qualities, 4, width));
+        lines.push("  Missing".into());
+        if missing.is_empty() {
+            lines.push("    none: every root has every quality".into());
+        } else {
+            lines.extend(fill(&missing, 4, width));
+        }
+        lines
+    }
+
+    /// Theory breakdown of the focused chord for the details pane.
+    fn detail_lines(&self) -> Option<Vec<String>> {
+        let Some(Panel::Chord {
+            key,
+            voicings,
+            voicing,
+        }) = self.focus.and_then(|f| self.panels.get(f))
+        else {
+            return None;
+        };
+        let chord = &voicings[*voicing];
+        // Spell everything the way the user wrote the chord (Bb7, not A#7)
+        let name = chord.spelled_name(key.split(' ').next().unwrap_or(key));
+        let (root, quality) = theory::split_root(name)?;
+        let formula = theory::quality_formula(quality).unwrap_or(&[]);
+        let others: Vec<&str> = chord.names().filter(|n| *n != name).collect();
+
+        let mut lines = vec![
+            format!("{}  (voicing {} of {})", name, voicing + 1, voicings.len()),
+            String::new(),
+            format!("Formula   {}", formula.join(" ")),
+            format!(
+                "Aliases   {}",
+                if others.is_empty() {
+                    "—".to_string()
+                } else {
+                    others.join(", ")
+                }
+            ),
+            format!(
+                "Source    {}",
+                self.library
+                    .source_of(chord)
+                    .map_or("derived".to_string(), |s| s.to_string())
+            ),
+            String::new(),
+            "String  Fret  Note  Interval".into(),
+        ];
+
+        // Spell each string's note against the chord's formula where it fits
+        let root = theory::spelling_root(root, formula);
+        let mut sounding = Vec::new();
+        let strings = theory::string_names(self.config.tuning, self.config.accidentals);
+        for (i, (string, fret)) in strings.iter().zip(chord.frets).enumerate() {
+            let Some(fret) = fret else {
+                lines.push(format!("  {:<2}      X", string));
+                continue;
+            };
+            let midi = self.config.tuning[i] + fret;
+            let (note, interval) =
+                theory::spell_note(root, formula, midi, self.config.accidentals)?;
+            lines.push(format!(
+                "  {:<2}   {:>4}    {:<4}  {}",
+                string, fret, note, interval
+            ));
+            sounding.push((midi, note));
+        }
+
+        // Re-entrant tuning means string order isn't pitch order
+        sounding.sort_unstable();
+        lines.push(String::new());
+        lines.push("Sounding, low to high".into());
+        lines.push(format!(
+            "  {}",
+            sounding
+                .iter()
+                .map(|(midi, note)| theory::spelled_pitch(note, *midi))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ));
+
+        lines.push(String::new());
+        lines.push(format!(
+            "Voicings ({} / {} to cycle)",
+            self.config.keys.key(Action::Voicing),
+            self.config.keys.key(Action::PrevVoicing)
+        ));
+        for (i, v) in voicings.iter().enumerate() {
+            let frets = v
+                .frets
+                .iter()
+                .map(|f| f.map_or("X".to_string(), |n| n.to_string()))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let marker = if i == *voicing { "▸" } else { " " };
+            let from_library = self
+                .library
+                .chords
+                .iter()
+                .any(|c| c.frets == v.frets && c.matches_name(&v.name));
+            let source = if from_library { "library" } else { "derived" };
+            lines.push(format!("{} {}   {}", marker, frets, source));
+        }
+        Some(lines)
+    }
+
+    /// Furthest the diagram pane can scroll before running out of rows.
+    fn max_scroll(&self) -> u16 {
+        self.diagram_rows.saturating_sub(self.diagram_view)
+    }
+
+    /// Scroll the diagram pane by `delta` rows, clamped to the content.
+    fn scroll_by(&mut self, delta: i32) {
+        let next = (self.scroll as i32 + delta).clamp(0, self.max_scroll() as i32);
+        self.scroll = next as u16;
+    }
+
+    /// Hand keyboard focus to the diagram grid, if it holds any chords.
+    fn enter_grid(&mut self) {
+        if self.focus.is_none() {
+            self.focus = self.panels.iter().position(|p| p.chord().is_some());
+        }
+        self.grid_active = self.focus.is_some();
+    }
+
+    fn leave_grid(&mut self) {
+        self.grid_active = false;
+        self.details_shown = false;
+    }
+
+    /// Focus the next (`delta` = 1) or previous (-1) chord in reading order.
+    fn focus_step(&mut self, delta: isize) {
+        let chords: Vec<usize> = (0..self.panels.len())
+            .filter(|&i| self.panels[i].chord().is_some())
+            .collect();
+        let Some(pos) = self.focus.and_then(|f| chords.iter().position(|&i| i == f)) else {
+            return;
+        };
+        let next = (pos as isize + delta).clamp(0, chords.len() as isize - 1);
+        self.focus = Some(chords[next as usize]);
+    }
+
+    /// Focus the chord in the grid row above (-1) or below (1), nearest in column.
+    fn focus_row(&mut self, delta: isize) {
+        let Some(cur) = self.focus.and_then(|f| self.grid_cells.get(f)).copied() else {
+            return;
+        };
+        let target = cur.row as isize + delta;
+        let best = self
+            .grid_cells
+            .iter()
+            .enumerate()
+            .filter(|&(i, c)| {
+                c.row as isize == target && self.panels.get(i).is_some_and(|p| p.chord().is_some())
+            })
+            .min_by_key(|(_, c)| c.x.abs_diff(cur.x));
+        if let Some((i, _)) = best {
+            self.focus = Some(i);
+        }
+    }
+
+    /// Show the focused chord's next (`delta` = 1) or previous (-1) voicing.
+    fn cycle_voicing(&mut self, delta: isize) {
+        if let Some(Panel::Chord {
+            voicings, voicing, ..
+        }) = self.focus.and_then(|f| self.panels.get_mut(f))
+        {
+            let n = voicings.len() as isize;
+            *voicing = (*voicing as isize + delta).rem_euclid(n) as usize;
+            self.status = Some(format!("Voicing {} of {}", *voicing + 1, n));
+        }
+    }
+
+    /// Drop the focused chord from the progression.
+    fn remove_focused(&mut self) {
+        let Some(f) = self.focus else {
+            return;
+        };
+        self.panels.remove(f);
+        let next = (f..self.panels.len())
+            .chain((0..f).rev())
+            .find(|&i| self.panels[i].chord().is_some());
+        self.focus = next;
+        if next.is_none() {
+            self.leave_grid();
+        }
+    }
+
+    /// Swap the focused chord with its neighbour on the left (-1) or right (1).
+    fn move_focused(&mut self, delta: isize) {
+        let Some(f) = self.focus else {
+            return;
+        };
+        let to = f as isize + delta;
+        if to >= 0 && (to as usize) < self.panels.len() {
+            self.panels.swap(f, to as usize);
+            self.focus = Some(to as usize);
+        }
+    }
+
+    /// Queue the focused diagram for the terminal clipboard.
+    fn copy_focused(&mut self) {
+        let Some(f) = self.focus else {
+            return;
+        };
+        if let Some(diagram) = self.diagrams().into_iter().nth(f) {
+            self.clipboard = Some(diagram.text());
+            let name = self.panels[f].chord().map_or("", |c| c.name.as_str());
+            self.status = Some(format!("Copied {} to the clipboard", name));
+        }
+    }
+
+    /// Open the focused chord's shape in the builder for editing.
+    fn edit_focused(&mut self) {
+        let Some(chord) = self.focus.and_then(|f| self.panels[f].chord()) else {
+            return;
+        };
+        let builder = Builder::editing(chord, &self.config);
+        if let Err(e) = builder.validate() {
+            self.status = Some(e);
+        }
+        self.builder = Some(builder);
+        self.leave_grid();
+    }
+
+    /// Refresh the autocomplete popup for the entry under the cursor.
+    fn update_completions(&mut self) {
+        self.completion_selected = None;
+        let text = self.input.text();
+        self.completions = if strip_prefix_ignore_case(text, "notes:").is_some() {
+            Vec::new()
+        } else {
+            let entry = complete::current_entry(text, self.input.cursor());
+            self.completer.candidates(&text[entry], 8)
+        };
+    }
+
+    fn close_completions(&mut self) {
+        self.completions.clear();
+        self.completion_selected = None;
+    }
+
+    /// Move the popup highlight by `delta`, wrapping around.
+    fn cycle_completion(&mut self, delta: isize) {
+        let n = self.completions.len() as isize;
+        let next = match self.completion_selected {
+            Some(i) => (i as isize + delta).rem_euclid(n),
+            None if delta < 0 => n - 1,
+            None => 0,
+        };
+        self.completion_selected = Some(next as usize);
+    }
+
+    /// Replace the entry under the cursor with the highlighted completion.
+    fn accept_completion(&mut self) {
+        if let Some(name) = self
+            .completion_selected
+            .and_then(|i| self.completions.get(i))
+        {
+            let entry = complete::current_entry(self.input.text(), self.input.cursor());
+            self.input.replace_range(entry, name);
+        }
+        self.close_completions();
+    }
+
+    /// Reverse lookup: every library or theory-derived chord whose notes match,
+    /// contain, or are contained in the given notes, best fit first.
+    fn lookup_notes(&mut self, notes: &str) {
+        let mut query = PitchSet::default();
+        for token in notes.split([' ', ',']).filter(|t| !t.is_empty()) {
+            match theory::parse_note(token) {
+                Some(pc) => query.insert(pc),
+                None => {
+                    self.panels
+                        .push(Panel::Message(format!("Unknown note: {}", token)));
+                    return;
                 }
-                let start = if has_open || gmin < 2 { 1 } else { gmin };
-                let end = std::cmp::max(gmax, start + 4);
-                for (key, chord) in selected {
-                    let mut d = chord.render_range(start, end);
-                    if let Some(pos) = d.find('\n') {
-                        let rest = &d[pos..];
-                        d = format!("Chord: {}\n{}", key, rest);
+            }
+        }
+        if query.is_empty() {
+            self.panels.push(Panel::Message(
+                "Please enter one or more notes, e.g. notes: C E G Bb".into(),
+            ));
+            return;
+        }
+
+        // Library shapes, compared by the notes they actually sound
+        let mut found: Vec<NoteMatch> = self
+            .library
+            .chords
+            .iter()
+            .filter_map(|c| {
+                Some(NoteMatch {
+                    fit: Fit::classify(c.pitch_set(self.config.tuning), query)?,
+                    chord: c.clone(),
+                    derived: false,
+                })
+            })
+            .collect();
+
+        // Theory-derived chords for every root and quality; keep whichever of a
+        // library shape or the textbook spelling fits better under each name
+        for root in 0..12 {
+            for (quality, _) in &theory::QUALITIES {
+                let Some(set) = theory::chord_pitch_set(root, quality) else {
+                    continue;
+                };
+                let name = format!(
+                    "{}{}",
+                    theory::note_name(root, self.config.accidentals),
+                    quality
+                );
+                let Some(fit) = Fit::classify(set, query) else {
+                    continue;
+                };
+                match found.iter().position(|m| m.chord.matches_name(&name)) {
+                    Some(i) if found[i].fit.rank() <= fit.rank() => continue,
+                    Some(i) => {
+                        found.remove(i);
                     }
-                    self.diagrams.push(d);
+                    None => {}
+                }
+                let Some(&frets) = self.voicings(set, Some(root + 7)).first() else {
+                    continue;
+                };
+                if let Some(chord) = Chord::new(name, frets) {
+                    found.push(NoteMatch {
+                        chord,
+                        fit,
+                        derived: true,
+                    });
                 }
             }
         }
-        self.input.clear();
-        self.scroll = 0;
+
+        if found.is_empty() {
+            self.panels.push(Panel::Message(format!(
+                "No chords match notes: {}",
+                notes.trim()
+            )));
+            return;
+        }
+        found.sort_by(|a, b| a.order().cmp(&b.order()));
+        for m in found {
+            let key = format!("{} {}", m.chord.name, m.fit.label(self.config.accidentals));
+            let panel = self.chord_panel(key, m.chord);
+            self.panels.push(panel);
+        }
+    }
+}
+
+/// How a candidate chord's notes relate to the notes the user asked for.
+enum Fit {
+    Exact,
+    /// The chord has every requested note plus these.
+    Superset(PitchSet),
+    /// The chord lacks these requested notes but adds nothing else.
+    Subset(PitchSet),
+}
+
+impl Fit {
+    fn classify(chord: PitchSet, query: PitchSet) -> Option<Fit> {
+        if chord.is_empty() {
+            None
+        } else if chord == query {
+            Some(Fit::Exact)
+        } else if query.is_subset(chord) {
+            Some(Fit::Superset(chord.difference(query)))
+        } else if chord.is_subset(query) {
+            Some(Fit::Subset(query.difference(chord)))
+        } else {
+            None
+        }
+    }
+
+    /// Sort key: fewer differing notes first, extra notes before missing ones.
+    fn rank(&self) -> (u32, u8) {
+        match self {
+            Fit::Exact => (0, 0),
+            Fit::Superset(extra) => (extra.len(), 1),
+            Fit::Subset(missing) => (missing.len(), 2),
+        }
+    }
+
+    fn label(&self, accidentals: Accidentals) -> String {
+        let names = |set: &PitchSet, sign: &str| {
+            set.iter()
+                .map(|pc| format!("{}{}", sign, theory::note_name(pc, accidentals)))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        match self {
+            Fit::Exact => "(exact)".into(),
+            Fit::Superset(extra) => format!("({})", names(extra, "+")),
+            Fit::Subset(missing) => format!("({})", names(missing, "-")),
+        }
+    }
+}
+
+/// A chord found by a note search.
+struct NoteMatch {
+    chord: Chord,
+    fit: Fit,
+    /// Built from the quality formula rather than taken from the library
+    derived: bool,
+}
+
+impl NoteMatch {
+    /// Sort key: best fit first, library shapes before derived ones, then
+    /// by name.
+    fn order(&self) -> ((u32, u8), bool, &str) {
+        (self.fit.rank(), self.derived, &self.chord.name)
+    }
+}
+
+/// A `:sources` panel describing one library layer.
+fn layer_panel(layer: &library::Layer) -> Panel {
+    let mut msg = format!("Source: {}\n", layer.source);
+    if let Some(error) = &layer.error {
+        msg.push_str(&format!("Unreadable: {}", error));
+        return Panel::Message(msg);
+    }
+    if !layer.found {
+        msg.push_str("Not found");
+        return Panel::Message(msg);
+    }
+    let plural = if layer.count == 1 { "" } else { "s" };
+    msg.push_str(&format!("{} chord{}", layer.count, plural));
+    if !layer.overrides.is_empty() {
+        msg.push_str(&wrapped("Overrides:", &layer.overrides));
+    }
+    if !layer.bad_lines.is_empty() {
+        let lines: Vec<String> = layer.bad_lines.iter().map(|n| n.to_string()).collect();
+        msg.push_str(&wrapped("Unparsed lines:", &lines));
+    }
+    if !layer.past_neck.is_empty() {
+        let lines: Vec<String> = layer.past_neck.iter().map(|(n, _)| n.to_string()).collect();
+        msg.push_str(&wrapped("Lines past the end of the neck:", &lines));
+    }
+    Panel::Message(msg)
+}
+
+/// `heading` followed by `items` on indented lines of at most 60 columns.
+fn wrapped(heading: &str, items: &[String]) -> String {
+    let mut out = format!("\n{}", heading);
+    for line in fill(items, 2, 60) {
+        out.push('\n');
+        out.push_str(&line);
+    }
+    out
+}
+
+/// Pack `items` two spaces apart into lines of at most `width` columns,
+/// each indented by `indent`. An item is never split.
+fn fill(items: &[String], indent: usize, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for item in items {
+        let len = UnicodeWidthStr::width(line.as_str());
+        if !line.is_empty() && len + 2 + UnicodeWidthStr::width(item.as_str()) > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if line.is_empty() {
+            line = " ".repeat(indent);
+        } else {
+            line.push_str("  ");
+        }
+        line.push_str(item);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// A help row with every case-insensitive occurrence of `query` (already
+/// lowercase) in `style`.
+fn highlighted(row: &str, query: &str, style: Style) -> Line<'static> {
+    if query.is_empty() {
+        return Line::from(row.to_string());
+    }
+    // ASCII lowercasing keeps byte offsets, so they index `row` too
+    let lower = row.to_ascii_lowercase();
+    let mut spans = Vec::new();
+    let mut at = 0;
+    for (start, _) in lower.match_indices(query) {
+        spans.push(Span::raw(row[at..start].to_string()));
+        spans.push(Span::styled(
+            row[start..start + query.len()].to_string(),
+            style,
+        ));
+        at = start + query.len();
+    }
+    spans.push(Span::raw(row[at..].to_string()));
+    Line::from(spans)
+}
+
+/// Word-wrap a line to `width` columns, continuing under its own indent.
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    if UnicodeWidthStr::width(line) <= width {
+        return vec![line.to_string()];
+    }
+    let text = line.trim_start();
+    let indent = line.len() - text.len();
+    let mut rows = Vec::new();
+    let mut row = " ".repeat(indent);
+    let mut fresh = true;
+    for word in text.split(' ') {
+        let len = UnicodeWidthStr::width(row.as_str()) + 1 + UnicodeWidthStr::width(word);
+        if !fresh && len > width {
+            rows.push(std::mem::take(&mut row));
+            row = " ".repeat(indent + 2);
+            fresh = true;
+        }
+        if !fresh {
+            row.push(' ');
+        }
+        row.push_str(word);
+        fresh = false;
     }
+    rows.push(row);
+    rows
+}
+
+/// Apply a line-editing key to a text field; returns whether it was one.
+/// Printable keys without a binding are typed.
+fn edit_input(input: &mut LineEditor, keys: &KeyBindings, key: &KeyEvent) -> bool {
+    match keys.actions(Mode::Edit, key).next() {
+        Some(Action::Left) => input.left(),
+        Some(Action::Right) => input.right(),
+        Some(Action::WordLeft) => input.word_left(),
+        Some(Action::WordRight) => input.word_right(),
+        Some(Action::LineStart) => input.home(),
+        Some(Action::LineEnd) => input.end(),
+        Some(Action::DeleteChar) => input.delete(),
+        Some(Action::Backspace) => input.backspace(),
+        Some(Action::KillToStart) => input.kill_to_start(),
+        Some(Action::KillToEnd) => input.kill_to_end(),
+        Some(Action::KillWord) => input.kill_word(),
+        Some(_) => return false,
+        None => match key.code {
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                input.insert(c)
+            }
+            _ => return false,
+        },
+    }
+    true
+}
+
+/// Standard base64 with padding, for clipboard escape sequences.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Strip `prefix` from the start of `s`, ignoring ASCII case.
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &s[prefix.len()..])
 }
 
 pub fn run_tui(mut app: App) -> io::Result<()> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut term = Terminal::new(backend)?;
 
@@ -113,34 +1529,6 @@ pub fn run_tui(mut app: App) -> io::Result<()> {
             let area = f.area();
 
             if app.help_shown {
-                // Build help text
-                let lines: Vec<String> = vec![
-                    "Help — Keybindings".into(),
-                    "".into(),
-                    "Enter   : lookup chords".into(),
-                    "↑ / ↓   : scroll diagrams".into(),
-                    "?       : show/hide this help".into(),
-                    "Esc/C-c : quit help or exit".into(),
-                    "".into(),
-                    "Usage: [Note][Accidental][Type], where".into(),
-                    "Note = C, D, E, F, G, A, B".into(),
-                    "Accidental = None, #, b".into(),
-                    "Type = None (default = maj), m, 7, maj7, m7, dim7, m7b5, 9, maj9, m9, 6, m6, add9, madd9, sus2, sus4, 7sus2, 7sus4, 7+5, 7b5, mM7, 6/9, aug, dim, add11, madd11".into(),
-                    // "Supported chords:".into(),
-                    "".into(),
-                    "Example: C, Ebm, G#m7sus4".into(),
-                ];
-                // Single long line of all chord names:
-                // let names = app
-                //     .chords
-                //     .iter()
-                //     .map(|c| c.name.clone())
-                //     .collect::<Vec<_>>()
-                //     .join(", ");
-                // lines.push(names);
-
-                let help_text = lines.join("\n");
-
                 // Centered help box
                 let block_area = {
                     let w = area.width.saturating_sub(10);
@@ -149,18 +1537,113 @@ pub fn run_tui(mut app: App) -> io::Result<()> {
                     let y = area.y + (area.height - h) / 2;
                     Rect::new(x, y, w, h)
                 };
+                let width = block_area.width.saturating_sub(2) as usize;
+
+                // Key bindings come straight from the keymap and the chord
+                // overview from the library, so neither can drift
+                let mut lines: Vec<String> = vec!["Help — Keybindings".into(), "".into()];
+                lines.extend(app.config.keys.help());
+                lines.extend([
+                    "Also".into(),
+                    "  mouse          wheel scrolls; click the builder's fretboard to place fingers".into(),
+                    "".into(),
+                    "Commands".into(),
+                    "  :sources       list the library files and what each one adds or overrides".into(),
+                    "  :song FILE     show a ChordPro song's chords, reloading them as it changes".into(),
+                    "  :build [chord] open the chord builder, optionally starting from a chord".into(),
+                    "  :export FORMAT [file]  save the chords shown as svg, html, markdown, text or midi".into(),
+                    "                 (default file: chords-sheet.svg, .html, .md, .txt or .mid)".into(),
+                    "".into(),
+                ]);
+                lines.extend(app.library_help(width));
+                lines.extend([
+                    "".into(),
+                    "Usage: a root (C D E F G A B, optionally followed by # or b) and a quality".into(),
+                    "Case doesn't matter except M (major) and m (minor): CMAJ7 is Cmaj7, CM7 isn't Cm7".into(),
+                    "Example: C, Ebm, G#7sus4".into(),
+                    "".into(),
+                    "Search by notes: notes: C E G Bb".into(),
+                ]);
+
+                // Wrap here rather than in the paragraph so a row index is a scroll offset
+                let rows: Vec<String> = lines.iter().flat_map(|l| wrap_line(l, width)).collect();
+                let view = block_area.height.saturating_sub(2);
+                app.help_view = view;
+                let max_scroll = (rows.len() as u16).saturating_sub(view);
+
+                // Search: find the matching rows and move to the one asked for
+                let mut title_bottom = None;
+                let mut query = String::new();
+                let mut current = None;
+                if let Some(search) = app.help_search.as_mut() {
+                    query = search.query.text().to_ascii_lowercase();
+                    let matches: Vec<usize> = rows
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, row)| !query.is_empty() && row.to_ascii_lowercase().contains(&query))
+                        .map(|(i, _)| i)
+                        .collect();
+                    if let Some(step) = search.jump.take() {
+                        let from = search.current.unwrap_or(app.help_scroll as usize);
+                        let found = match step {
+                            0 => matches.iter().find(|&&r| r >= from),
+                            1 => matches.iter().find(|&&r| r > from),
+                            _ => matches.iter().rev().find(|&&r| r < from),
+                        };
+                        // Wrap around at either end
+                        let found = found.or(if step < 0 { matches.last() } else { matches.first() });
+                        search.current = found.copied();
+                        if let Some(&row) = found
+                            && (row < app.help_scroll as usize || row >= (app.help_scroll + view) as usize)
+                        {
+                            app.help_scroll = (row as u16).saturating_sub(2);
+                        }
+                    }
+                    current = search.current.filter(|c| matches.contains(c));
+                    let position = match current {
+                        Some(c) => format!(
+                            "{} of {}",
+                            matches.iter().position(|&m| m == c).unwrap_or(0) + 1,
+                            matches.len()
+                        ),
+                        None if query.is_empty() => "type to search".into(),
+                        None => "no matches".into(),
+                    };
+                    let cursor = if search.typing { "▏" } else { "" };
+                    title_bottom = Some(format!(
+                        " /{}{} — {} ",
+                        search.query.text(),
+                        cursor,
+                        position
+                    ));
+                }
+                app.help_scroll = app.help_scroll.min(max_scroll);
+
+                let theme = &app.config.theme;
+                let text: Vec<Line> = rows
+                    .iter()
+                    .enumerate()
+                    .map(|(i, row)| {
+                        let mut style = theme.accent.add_modifier(Modifier::REVERSED);
+                        if current == Some(i) {
+                            style = style.add_modifier(Modifier::BOLD);
+                        }
+                        highlighted(row, &query, style)
+                    })
+                    .collect();
 
                 // Render clear background + help
                 f.render_widget(Clear, block_area);
-                let help_para = Paragraph::new(help_text)
-                    .wrap(Wrap { trim: true })
+                let mut block = Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Help ")
+                    .border_style(theme.accent);
+                if let Some(title) = title_bottom {
+                    block = block.title_bottom(Line::from(title));
+                }
+                let help_para = Paragraph::new(text)
                     .scroll((app.help_scroll, 0))
-                    .block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .title(" Help ")
-                            .border_style(Style::default().fg(Color::Yellow)),
-                    )
+                    .block(block)
                     .alignment(Alignment::Left);
                 f.render_widget(help_para, block_area);
             } else {
@@ -177,97 +1660,293 @@ pub fn run_tui(mut app: App) -> io::Result<()> {
                     .split(area);
 
                 // Input box
-                let input = Paragraph::new(app.input.as_str())
+                // Scroll sideways so the cursor stays inside the box
+                let text = app.input.text();
+                let cursor_w = UnicodeWidthStr::width(&text[..app.input.cursor()]) as u16;
+                let inner_w = chunks[0].width.saturating_sub(2);
+                let hscroll = (cursor_w + 1).saturating_sub(inner_w);
+                let input = Paragraph::new(text)
+                    .scroll((0, hscroll))
                     .block(Block::default().borders(Borders::ALL).title("Chord(s)"));
                 f.render_widget(input, chunks[0]);
 
-                // Blinking cursor at end of input
-                let x = chunks[0].x + 1 + UnicodeWidthStr::width(app.input.as_str()) as u16;
+                // Blinking cursor at the edit position
+                let x = chunks[0].x + 1 + cursor_w - hscroll;
                 let y = chunks[0].y + 1;
                 f.set_cursor_position((x, y));
 
                 // Diagrams grid
-                // let max_w = chunks[1].width as usize;
-                // let rows = combine_diagrams_grid(&app.diagrams, max_w, 2);
-                // let text = rows.join("\n");
-                // let diags = Paragraph::new(text)
-                //     .scroll((app.scroll, 0))
-                //     .block(
-                //         Block::default()
-                //             .borders(Borders::ALL)
-                //             .title("Diagrams")
-                //             .border_style(Style::default().add_modifier(Modifier::BOLD)),
-                //     );
-                // f.render_widget(diags, chunks[1]);
-                let area = chunks[1];
-                let text_block = if app.diagrams.len() == 1
-                    && app.diagrams[0].starts_with("Type comma separated")
-                {
-                    // INITIAL LOGO + PROMPT
-                    let box_width = area.width as usize;
-
-                    // ASCII art
-                    let lines = vec![
-                        "     @@@@@@@@                                                        ".to_string(),
-                        "   @@@      @@@@         @@       uke-tui                            ".to_string(),
-                        "  @@@          @@@@@  @@@@@@@@    ishan                              ".to_string(),
-                        " @@               @@@@@   @@@@@   https://github.com/ishan-dubey     ".to_string(),
-                        " @@          ##              @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@        ".to_string(),
-                        "@@           #────,**,────────────────────────────────X   X@@@       ".to_string(),
-                        "@@           #───(,,,,)──────────────────────────────────/   @@@     ".to_string(),
-                        " @@          #──((,,,,))─────────────────────────────────\\     @@@   ".to_string(),
-                        " @@          #───(,,,,)───────────────────────────────X   X      @@@ ".to_string(),
-                        "  @@         ##   ****        @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@".to_string(),
-                        "   @@            @@@@       @@@                                      ".to_string(),
-                        "   @@@@@    @@@@@@  @@@@@@@@@                                        ".to_string(),
-                        "      @@@@@@@@                                                       ".to_string(),
-                        // Prompt
-                        "".to_string(),
-                        "Type a chord and press Enter".to_string(),
-                        "".to_string(),
-                    ];
-
-                    // Center each line horizontally
-                    lines
-                        .into_iter()
-                        .map(|line| {
-                            let w = UnicodeWidthStr::width(line.as_str());
-                            if box_width > w {
-                                let left = (box_width - w) / 2;
-                                " ".repeat(left) + &line
-                            } else {
-                                line
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                        .join("\n")
+                let mut area = chunks[1];
+
+                if let Some(builder) = &app.builder {
+                    let title = match &builder.source {
+                        Some(name) => format!("Chord builder — editing {}", name),
+                        None => "Chord builder".to_string(),
+                    };
+                    let block = Block::default()
+                        .borders(Borders::ALL)
+                        .title(title)
+                        .border_style(Style::default().add_modifier(Modifier::BOLD));
+                    let inner = block.inner(area);
+                    app.builder_origin = (inner.x, inner.y);
+
+                    let names = builder.names(&app.library.chords);
+                    // Colour the fingers by the name the shape would be saved as
+                    let tones = builder
+                        .default_name(&app.library.chords)
+                        .and_then(|name| Chord::new(name, builder.frets()))
+                        .map(|chord| chord.tones(app.config.tuning));
+                    let mut board = builder.fretboard().theme(app.config.theme);
+                    if let Some(tones) = tones {
+                        board = board.tones(tones);
+                    }
+                    let board_height = board.size().1;
+                    let text_area = Rect {
+                        y: inner.y + board_height.min(inner.height),
+                        height: inner.height.saturating_sub(board_height),
+                        ..inner
+                    };
+                    let mut lines = vec![String::new()];
+                    lines.push(format!(
+                        "Name   {}",
+                        if names.is_empty() {
+                            "—".to_string()
+                        } else {
+                            names.join(" / ")
+                        }
+                    ));
+                    if let Some(name) = builder.default_name(&app.library.chords) {
+                        let frets = builder
+                            .frets()
+                            .map(|f| f.map_or("X".to_string(), |n| n.to_string()))
+                            .join(" ");
+                        lines.push(format!("Line   {} = {}", name, frets));
+                    }
+                    lines.push(String::new());
+                    let key = |action| app.config.keys.key(action);
+                    lines.push(format!(
+                        "Click a fret, or move with {}{}{}{} and press {}, to place or lift a finger.",
+                        key(Action::FretLeft),
+                        key(Action::StringDown),
+                        key(Action::StringUp),
+                        key(Action::FretRight),
+                        key(Action::Toggle),
+                    ));
+                    lines.push(format!(
+                        "Beside the nut: open / mute.  {}: mute  {}/{}: slide finger  {} / {}: undo / redo",
+                        key(Action::Mute),
+                        key(Action::SlideUp),
+                        key(Action::SlideDown),
+                        key(Action::Undo),
+                        key(Action::Redo),
+                    ));
+                    lines.push(format!(
+                        "{}: save to library  {}: show in grid  {}: clear  {}: close",
+                        key(Action::Save),
+                        key(Action::Show),
+                        key(Action::Clear),
+                        key(Action::Close),
+                    ));
+                    if let Some(naming) = &builder.naming {
+                        lines.push(String::new());
+                        let prompt = "Save as: ";
+                        lines.push(format!("{}{}", prompt, naming.text()));
+                        let x = inner.x
+                            + UnicodeWidthStr::width(prompt) as u16
+                            + UnicodeWidthStr::width(&naming.text()[..naming.cursor()]) as u16;
+                        let y = text_area.y + lines.len() as u16 - 1;
+                        f.set_cursor_position((x, y));
+                    }
+                    f.render_widget(block, area);
+                    f.render_widget(board, inner);
+                    f.render_widget(Paragraph::new(lines.join("\n")), text_area);
                 } else {
-                    // Normal grid
-                    let max_w = area.width as usize;
-                    let rows = combine_diagrams_grid(&app.diagrams, max_w, 2);
-                    rows.join("\n")
-                };
+                    // Details pane for the focused chord, beside the grid
+                    if app.grid_active
+                        && app.details_shown
+                        && let Some(lines) = app.detail_lines()
+                    {
+                        let split = Layout::default()
+                            .direction(Direction::Horizontal)
+                            .constraints([Constraint::Min(20), Constraint::Length(36)])
+                            .split(area);
+                        area = split[0];
+                        let details = Paragraph::new(lines.join("\n")).block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .title(" Details ")
+                                .border_style(app.config.theme.accent),
+                        );
+                        f.render_widget(details, split[1]);
+                    }
+
+                    let mut content_rows = 0;
+                    let text_block: Text = if matches!(
+                        app.panels.as_slice(),
+                        [Panel::Message(m)] if m.starts_with("Type comma separated")
+                    ) {
+                        // INITIAL LOGO + PROMPT
+                        let box_width = area.width as usize;
 
-                // Render it
-                let diags = Paragraph::new(text_block)
-                    .block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .title("Diagrams")
-                            .border_style(Style::default().add_modifier(Modifier::BOLD)),
-                    );
-                f.render_widget(diags, area);
+                        // ASCII art
+                        let lines = vec![
+                            "     @@@@@@@@                                                        ".to_string(),
+                            "   @@@      @@@@         @@       uke-tui                            ".to_string(),
+                            "  @@@          @@@@@  @@@@@@@@    ishan                              ".to_string(),
+                            " @@               @@@@@   @@@@@   https://github.com/ishan-dubey     ".to_string(),
+                            " @@          ##              @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@        ".to_string(),
+                            "@@           #────,**,────────────────────────────────X   X@@@       ".to_string(),
+                            "@@           #───(,,,,)──────────────────────────────────/   @@@     ".to_string(),
+                            " @@          #──((,,,,))─────────────────────────────────\\     @@@   ".to_string(),
+                            " @@          #───(,,,,)───────────────────────────────X   X      @@@ ".to_string(),
+                            "  @@         ##   ****        @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@".to_string(),
+                            "   @@            @@@@       @@@                                      ".to_string(),
+                            "   @@@@@    @@@@@@  @@@@@@@@@                                        ".to_string(),
+                            "      @@@@@@@@                                                       ".to_string(),
+                            // Prompt
+                            "".to_string(),
+                            format!("Type a chord and press {}", app.config.keys.key(Action::Lookup)),
+                            "".to_string(),
+                        ];
+
+                        // Center each line horizontally
+                        lines
+                            .into_iter()
+                            .map(|line| {
+                                let w = UnicodeWidthStr::width(line.as_str());
+                                if box_width > w {
+                                    let left = (box_width - w) / 2;
+                                    " ".repeat(left) + &line
+                                } else {
+                                    line
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                            .into()
+                    } else {
+                        // Normal grid
+                        let max_w = area.width.saturating_sub(2) as usize;
+                        let focus = app.focus.filter(|_| app.grid_active);
+                        let (rows, cells) = combine_diagrams_grid(
+                            &app.diagrams(),
+                            focus,
+                            max_w,
+                            app.config.grid_spacing,
+                            &app.config.theme,
+                            None,
+                        );
+                        content_rows = rows.len();
+                        app.grid_cells = cells;
+                        rows.into()
+                    };
+
+                    // Keep the scroll offset inside the grid for the current pane size
+                    app.diagram_rows = content_rows as u16;
+                    app.diagram_view = area.height.saturating_sub(2);
+                    if app.grid_active
+                        && let Some(cell) = app.focus.and_then(|f| app.grid_cells.get(f))
+                    {
+                        // Bring the focused chord into view
+                        let bottom = cell.top + cell.height;
+                        if bottom > app.scroll + app.diagram_view {
+                            app.scroll = bottom.saturating_sub(app.diagram_view);
+                        }
+                        app.scroll = app.scroll.min(cell.top);
+                    }
+                    app.scroll = app.scroll.min(app.max_scroll());
+
+                    let mut block = Block::default()
+                        .borders(Borders::ALL)
+                        .title("Diagrams")
+                        .border_style(Style::default().add_modifier(Modifier::BOLD));
+                    if content_rows > 0 {
+                        let first = app.scroll + 1;
+                        let last = (app.scroll + app.diagram_view).min(app.diagram_rows);
+                        block = block.title_bottom(
+                            Line::from(format!(" rows {}–{} of {} ", first, last, app.diagram_rows))
+                                .right_aligned(),
+                        );
+                    }
+
+                    // Render it
+                    let diags = Paragraph::new(text_block)
+                        .scroll((app.scroll, 0))
+                        .block(block);
+                    f.render_widget(diags, area);
+
+                    if app.max_scroll() > 0 {
+                        let mut state = ScrollbarState::new(app.max_scroll() as usize)
+                            .position(app.scroll as usize)
+                            .viewport_content_length(app.diagram_view as usize);
+                        f.render_stateful_widget(
+                            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+                            area.inner(Margin::new(0, 1)),
+                            &mut state,
+                        );
+                    }
+                }
 
                 // Footer
-                let footer = Paragraph::new("Enter:lookup  ↑/↓:scroll  ?:help  Esc/C-c:quit")
-                    .style(Style::default().fg(Color::Gray))
+                let mode = if app.builder.as_ref().is_some_and(|b| b.naming.is_some()) {
+                    Mode::Prompt
+                } else if app.builder.is_some() {
+                    Mode::Builder
+                } else if app.grid_active {
+                    Mode::Grid
+                } else {
+                    Mode::Input
+                };
+                // A one-off message, else what the last reload couldn't
+                // read, else the key hints
+                let text = app.status.clone().unwrap_or_else(|| {
+                    if app.problems.is_empty() {
+                        app.config.keys.hints(mode)
+                    } else {
+                        app.problems.join("; ")
+                    }
+                });
+                let footer = Paragraph::new(text)
+                    .style(app.config.theme.hint)
                     .alignment(Alignment::Center);
                 f.render_widget(footer, chunks[2]);
+
+                // Autocomplete popup, anchored under the chord being typed
+                if !app.completions.is_empty() {
+                    let text = app.input.text();
+                    let entry = complete::current_entry(text, app.input.cursor());
+                    let offset = (UnicodeWidthStr::width(&text[..entry.start]) as u16)
+                        .saturating_sub(hscroll);
+                    let w = app
+                        .completions
+                        .iter()
+                        .map(|n| UnicodeWidthStr::width(n.as_str()))
+                        .max()
+                        .unwrap_or(0) as u16
+                        + 4;
+                    let h = app.completions.len() as u16 + 2;
+                    let x = (chunks[0].x + offset).min(area.right().saturating_sub(w));
+                    let y = chunks[0].bottom();
+                    let popup = Rect::new(x, y, w, h).intersection(area);
+
+                    let items: Vec<ListItem> = app
+                        .completions
+                        .iter()
+                        .map(|n| ListItem::new(n.as_str()))
+                        .collect();
+                    let list = List::new(items)
+                        .block(Block::default().borders(Borders::ALL))
+                        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+                    let mut state = ListState::default().with_selected(app.completion_selected);
+                    f.render_widget(Clear, popup);
+                    f.render_stateful_widget(list, popup, &mut state);
+                }
             }
         })?;
 
         // Show or hide terminal cursor
-        if app.help_shown {
+        let builder_typing = app.builder.as_ref().map(|b| b.naming.is_some());
+        if app.help_shown || app.grid_active || builder_typing == Some(false) {
             term.hide_cursor()?;
         } else {
             term.show_cursor()?;
@@ -278,92 +1957,46 @@ pub fn run_tui(mut app: App) -> io::Result<()> {
             .checked_sub(last_tick.elapsed())
             .unwrap_or_default();
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if app.help_shown {
-                    match key {
-                        KeyEvent {
-                            code: KeyCode::Esc, ..
-                        }
-                        | KeyEvent {
-                            code: KeyCode::Char('c'),
-                            modifiers: KeyModifiers::CONTROL,
-                            ..
-                        } => {
-                            app.help_shown = false;
-                        }
-                        KeyEvent {
-                            code: KeyCode::Up, ..
-                        } => {
-                            if app.help_scroll > 0 {
-                                app.help_scroll -= 1;
-                            }
-                        }
-                        KeyEvent {
-                            code: KeyCode::Down,
-                            ..
-                        } => {
-                            app.help_scroll = app.help_scroll.saturating_add(1);
-                        }
-                        _ => {}
+            match event::read()? {
+                Event::Key(key) if !app.handle_key(key) => break,
+                Event::Mouse(mouse) => match mouse.kind {
+                    MouseEventKind::ScrollUp if app.help_shown => {
+                        app.help_scroll = app.help_scroll.saturating_sub(3);
                     }
-                } else {
-                    match key {
-                        KeyEvent {
-                            code: KeyCode::Char('?'),
-                            ..
-                        } => {
-                            app.help_shown = true;
-                            app.help_scroll = 0;
-                        }
-                        KeyEvent {
-                            code: KeyCode::Esc, ..
-                        }
-                        | KeyEvent {
-                            code: KeyCode::Char('c'),
-                            modifiers: KeyModifiers::CONTROL,
-                            ..
-                        } => {
-                            break;
-                        }
-                        KeyEvent {
-                            code: KeyCode::Char(c),
-                            ..
-                        } => {
-                            app.input.push(c);
-                        }
-                        KeyEvent {
-                            code: KeyCode::Backspace,
-                            ..
-                        } => {
-                            app.input.pop();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Enter,
-                            ..
-                        } => {
-                            app.lookup();
-                        }
-                        KeyEvent {
-                            code: KeyCode::Up, ..
-                        } => {
-                            if app.scroll > 0 {
-                                app.scroll -= 1;
-                            }
-                        }
-                        KeyEvent {
-                            code: KeyCode::Down,
-                            ..
-                        } => {
-                            app.scroll = app.scroll.saturating_add(1);
+                    MouseEventKind::ScrollDown if app.help_shown => {
+                        app.help_scroll = app.help_scroll.saturating_add(3);
+                    }
+                    MouseEventKind::Down(MouseButton::Left) if app.builder.is_some() => {
+                        app.builder_click(mouse.column, mouse.row);
+                    }
+                    MouseEventKind::ScrollUp => app.scroll_by(-3),
+                    MouseEventKind::ScrollDown => app.scroll_by(3),
+                    _ => {}
+                },
+                Event::Paste(text) if !app.help_shown => {
+                    if let Some(builder) = app.builder.as_mut() {
+                        if let Some(naming) = builder.naming.as_mut() {
+                            naming.insert_str(&text);
                         }
-                        _ => {}
+                    } else {
+                        app.input.insert_str(&text);
+                        app.update_completions();
                     }
                 }
+                _ => {}
             }
         }
 
+        // Copy via OSC 52, which terminals forward to the system clipboard
+        if let Some(text) = app.clipboard.take() {
+            let backend = term.backend_mut();
+            write!(backend, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+            backend.flush()?;
+        }
+
         // Throttle loop
         if last_tick.elapsed() >= tick_rate {
+            app.on_tick();
             last_tick = Instant::now();
         }
     }
@@ -373,95 +2006,81 @@ pub fn run_tui(mut app: App) -> io::Result<()> {
     execute!(
         term.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     term.show_cursor()?;
     Ok(())
 }
 
-/// Arrange diagrams into rows that wrap at `max_width`, spacing them by `spacing` columns,
-/// and padding each line to the display‐width of its block.
-fn combine_diagrams_grid(diagrams: &[String], max_width: usize, spacing: usize) -> Vec<String> {
-    // Split into lines and compute each block’s display‐width & height
-    let blocks: Vec<Vec<String>> = diagrams
-        .iter()
-        .map(|d| d.lines().map(str::to_string).collect())
-        .collect();
-
-    let widths: Vec<usize> = blocks
-        .iter()
-        .map(|lines| {
-            lines
-                .iter()
-                .map(|l| UnicodeWidthStr::width(l.as_str()))
-                .max()
-                .unwrap_or(0)
-        })
-        .collect();
-
-    let heights: Vec<usize> = blocks.iter().map(|lines| lines.len()).collect();
-
-    // Pack block indices into rows
-    let mut rows: Vec<Vec<usize>> = Vec::new();
-    let mut cur: Vec<usize> = Vec::new();
-    let mut used_w = 0;
-
-    for (i, &w) in widths.iter().enumerate() {
-        let needed = if cur.is_empty() {
-            w
-        } else {
-            used_w + spacing + w
-        };
-        if needed > max_width && !cur.is_empty() {
-            rows.push(cur);
-            cur = vec![i];
-            used_w = w;
-        } else {
-            if cur.is_empty() {
-                used_w = w;
-            } else {
-                used_w = used_w + spacing + w;
-            }
-            cur.push(i);
-        }
-    }
-    if !cur.is_empty() {
-        rows.push(cur);
-    }
-
-    // Build each output line
-    let mut out: Vec<String> = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for row in rows {
-        // How tall is this row?
-        let row_h = row.iter().map(|&i| heights[i]).max().unwrap_or(0);
-
-        for line_idx in 0..row_h {
-            let mut line = String::new();
-
-            for (j, &block_i) in row.iter().enumerate() {
-                let block = &blocks[block_i];
-                // Get the text for this line, or "" if the block has fewer lines
-                let cell = block.get(line_idx).map(String::as_str).unwrap_or("");
-                let disp = UnicodeWidthStr::width(cell);
-                let pad = widths[block_i].saturating_sub(disp);
-
-                line.push_str(cell);
-                // Pad with spaces to the block’s full width
-                line.push_str(&" ".repeat(pad));
-
-                // Spacing between blocks
-                if j + 1 < row.len() {
-                    line.push_str(&" ".repeat(spacing));
-                }
-            }
-
-            out.push(line);
+    fn notes(names: &str) -> PitchSet {
+        let mut set = PitchSet::default();
+        for name in names.split_whitespace() {
+            set.insert(theory::parse_note(name).expect("note"));
         }
+        set
+    }
 
-        // Blank separator row between block‐rows
-        out.push(String::new());
+    #[test]
+    fn classifies_how_a_chord_fits_the_notes() {
+        let query = notes("C E G");
+        assert!(matches!(
+            Fit::classify(notes("C E G"), query),
+            Some(Fit::Exact)
+        ));
+        assert!(
+            matches!(Fit::classify(notes("C E G Bb"), query), Some(Fit::Superset(extra)) if extra == notes("Bb"))
+        );
+        assert!(
+            matches!(Fit::classify(notes("C E"), query), Some(Fit::Subset(missing)) if missing == notes("G"))
+        );
+        // Both missing and extra notes, or nothing sounding, don't fit
+        assert!(Fit::classify(notes("C Eb G"), query).is_none());
+        assert!(Fit::classify(PitchSet::default(), query).is_none());
     }
 
-    out
+    #[test]
+    fn ranks_close_fits_first() {
+        let chord = |name: &str| Chord::new(name.into(), [Some(0); 4]).expect("chord");
+        let query = notes("C E G");
+        let found = |name: &str, set: &str, derived: bool| NoteMatch {
+            chord: chord(name),
+            fit: Fit::classify(notes(set), query).expect("fits"),
+            derived,
+        };
+        let mut matches = [
+            found("C6", "C E G A", false),
+            found("C5", "C G", false),
+            found("Cmaj7", "C E G B", true),
+            found("C", "C E G", true),
+            found("Cadd9", "C D E G", false),
+            found("C7", "C E G Bb", false),
+            found("C", "C E G", false),
+            found("Cdim", "C", false),
+        ];
+        matches.sort_by(|a, b| a.order().cmp(&b.order()));
+        let order: Vec<(&str, bool)> = matches
+            .iter()
+            .map(|m| (m.chord.name.as_str(), m.derived))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                ("C", false),
+                ("C", true),
+                ("C6", false),
+                ("C7", false),
+                ("Cadd9", false),
+                ("Cmaj7", true),
+                ("C5", false),
+                ("Cdim", false),
+            ]
+        );
+        assert_eq!(matches[2].fit.label(Accidentals::Flats), "(+A)");
+        assert_eq!(matches[6].fit.label(Accidentals::Flats), "(-E)");
+    }
 }
@@ -0,0 +1,89 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// What a file looked like when last checked; `None` while it doesn't exist.
+type Stamp = Option<(SystemTime, u64)>;
+
+/// Notices when any of a set of files is created, changed or removed. Polled
+/// from the UI tick, so it needs no platform file-notification support.
+pub struct Watcher {
+    files: Vec<(PathBuf, Stamp)>,
+}
+
+impl Watcher {
+    pub fn new(paths: &[PathBuf]) -> Watcher {
+        let files = paths.iter().map(|p| (p.clone(), stamp(p))).collect();
+        Watcher { files }
+    }
+
+    /// The files that differ from the last check.
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for (path, last) in &mut self.files {
+            let now = stamp(path);
+            if now != *last {
+                *last = now;
+                changed.push(path.clone());
+            }
+        }
+        changed
+    }
+}
+
+/// Modification time and size: size catches edits made within the
+/// filesystem's timestamp resolution.
+fn stamp(path: &Path) -> Stamp {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("uke-tui-watch-{}-{}", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn each_change_is_reported_once() {
+        let path = temp("changes");
+        let watched = std::slice::from_ref(&path);
+        let mut watcher = Watcher::new(watched);
+        assert!(watcher.changed().is_empty());
+
+        fs::write(&path, "C = 0 0 0 3\n").unwrap();
+        assert_eq!(watcher.changed(), watched);
+        assert!(watcher.changed().is_empty());
+
+        // Rewritten at once, perhaps within the same timestamp: the new size
+        // still gives it away
+        fs::write(&path, "C = 0 0 0 3\nAm = 2 0 0 0\n").unwrap();
+        assert_eq!(watcher.changed(), watched);
+        assert!(watcher.changed().is_empty());
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(watcher.changed(), watched);
+        assert!(watcher.changed().is_empty());
+    }
+
+    #[test]
+    fn unchanged_files_are_never_reported() {
+        let (kept, missing) = (temp("kept"), temp("missing"));
+        fs::write(&kept, "G = 0 2 3 2\n").unwrap();
+        let mut watcher = Watcher::new(&[kept.clone(), missing]);
+        for _ in 0..3 {
+            assert!(watcher.changed().is_empty());
+        }
+        // Reading it doesn't count
+        fs::read_to_string(&kept).unwrap();
+        assert!(watcher.changed().is_empty());
+        fs::remove_file(&kept).unwrap();
+    }
+}
@@ -0,0 +1,1208 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::Widget,
+};
+use serde::Deserialize;
+use unicode_width::UnicodeWidthStr;
+
+use crate::chords::{Chord, DEFAULT_FRET_WINDOW, fret_window};
+use crate::theme::Theme;
+use crate::theory::{self, Accidentals, STANDARD_TUNING, Tone};
+
+/// Frets on a `Fretboard` unless set otherwise.
+pub const DEFAULT_NECK_FRETS: u8 = 12;
+
+/// Columns before the first fret of a horizontal `Fretboard`, after the
+/// string name: a space, the bracketed open/muted marker and the nut.
+const NUT_WIDTH: u16 = 5;
+/// Columns per fret cell of a horizontal `Fretboard`, including the fret wire.
+const CELL_WIDTH: u16 = 4;
+/// Columns before the first string of a vertical `Fretboard`: the fret number.
+const FRET_LABEL_WIDTH: u16 = 3;
+/// Columns per string of a vertical `Fretboard`.
+const STRING_WIDTH: u16 = 4;
+
+/// What a piece of a rendered diagram shows, so a front end can style it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    /// Messages and anything else left unstyled
+    Text,
+    Title,
+    /// String names and fret numbers
+    Label,
+    /// The open-string marker
+    Open,
+    /// The muted-string marker
+    Muted,
+    /// Empty frets, strings, the nut and dividers
+    Fretboard,
+    /// A fretted note, by its role in the chord
+    Dot(Tone),
+    /// The brackets around a `Fretboard`'s cursor
+    Cursor,
+}
+
+/// A rendered diagram: lines of text pieces, each tagged with what it shows.
+#[derive(Debug, Clone, Default)]
+pub struct Diagram {
+    pub lines: Vec<Vec<(Part, String)>>,
+}
+
+impl Diagram {
+    /// Unstyled text, such as a message.
+    pub fn plain(text: &str) -> Diagram {
+        Diagram {
+            lines: text
+                .lines()
+                .map(|line| vec![(Part::Text, line.to_string())])
+                .collect(),
+        }
+    }
+
+    /// The diagram as plain text, e.g. for the clipboard.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        for line in &self.lines {
+            for (_, piece) in line {
+                out.push_str(piece);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Display width of the widest line.
+    pub fn width(&self) -> usize {
+        self.lines.iter().map(|l| line_width(l)).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// The lines as ratatui text, each piece styled by `theme`.
+    pub fn styled(&self, theme: &Theme) -> Vec<Line<'static>> {
+        self.lines
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|(part, piece)| Span::styled(piece.clone(), theme.part(*part)))
+                    .collect::<Line>()
+            })
+            .collect()
+    }
+
+    fn push(&mut self, part: Part, text: impl Into<String>) {
+        match self.lines.last_mut() {
+            Some(line) => line.push((part, text.into())),
+            None => self.lines.push(vec![(part, text.into())]),
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.lines.push(Vec::new());
+    }
+
+    /// Draw into `area`, clipping whatever doesn't fit.
+    fn render(&self, theme: &Theme, area: Rect, buf: &mut Buffer) {
+        for (line, y) in self.styled(theme).iter().zip(area.top()..area.bottom()) {
+            buf.set_line(area.x, y, line, area.width);
+        }
+    }
+}
+
+/// Display width of one diagram line.
+pub fn line_width(line: &[(Part, String)]) -> usize {
+    line.iter()
+        .map(|(_, piece)| UnicodeWidthStr::width(piece.as_str()))
+        .sum()
+}
+
+/// Which way the strings run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    /// Strings as rows, frets left to right
+    #[default]
+    Horizontal,
+    /// Strings as columns, frets going down as in printed charts
+    Vertical,
+}
+
+impl Orientation {
+    /// The other one.
+    pub fn turned(self) -> Orientation {
+        match self {
+            Orientation::Horizontal => Orientation::Vertical,
+            Orientation::Vertical => Orientation::Horizontal,
+        }
+    }
+}
+
+/// Which string a horizontal diagram puts on top. Vertical diagrams are the
+/// horizontal ones turned a quarter clockwise, so that string ends up on the
+/// right.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum StringOrder {
+    /// A on top, as in tablature
+    #[default]
+    #[serde(rename = "a-on-top")]
+    ATop,
+    #[serde(rename = "g-on-top")]
+    GTop,
+}
+
+/// What a chord diagram writes for each fretted or open string.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Labels {
+    /// "●" and "O"
+    #[default]
+    Dots,
+    /// Note names spelled against the chord: C, E, G
+    Notes,
+    /// Intervals above the root: R, 3, 5, b7
+    Intervals,
+    /// Suggested fingers, 1 to 4
+    Fingers,
+}
+
+impl Labels {
+    /// The next kind, wrapping around, for cycling with one key.
+    pub fn next(self) -> Labels {
+        match self {
+            Labels::Dots => Labels::Notes,
+            Labels::Notes => Labels::Intervals,
+            Labels::Intervals => Labels::Fingers,
+            Labels::Fingers => Labels::Dots,
+        }
+    }
+}
+
+/// How diagrams are laid out, shared by the widgets and every export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct View {
+    pub orientation: Orientation,
+    /// Mirror left to right: the nut on the right of horizontal diagrams,
+    /// the strings reversed in vertical ones
+    pub left_handed: bool,
+    pub string_order: StringOrder,
+    /// What chord diagrams write on each string
+    pub labels: Labels,
+}
+
+impl View {
+    /// Strings in drawing order as indices into `Chord.frets`: top to bottom
+    /// in horizontal diagrams, left to right in vertical ones.
+    pub fn strings(&self) -> [usize; 4] {
+        let mut order = match self.string_order {
+            StringOrder::ATop => [3, 2, 1, 0],
+            StringOrder::GTop => [0, 1, 2, 3],
+        };
+        // Turning clockwise puts the top string on the right; mirroring
+        // swaps it back
+        if self.orientation == Orientation::Vertical && !self.left_handed {
+            order.reverse();
+        }
+        order
+    }
+
+    /// Whether frets run right to left, away from a nut on the right.
+    pub fn frets_reversed(&self) -> bool {
+        self.left_handed && self.orientation == Orientation::Horizontal
+    }
+}
+
+/// One chord shape over a window of frets, with its name on top.
+///
+/// ```no_run
+/// # use uke_tui::{chords::Chord, widgets::{ChordDiagram, Orientation}};
+/// # fn draw(frame: &mut ratatui::Frame, chord: &Chord) {
+/// let diagram = ChordDiagram::new(chord).window(1, 5).orientation(Orientation::Vertical);
+/// frame.render_widget(diagram, frame.area());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChordDiagram<'a> {
+    chord: &'a Chord,
+    title: Option<String>,
+    name: Option<String>,
+    window: Option<(u8, u8)>,
+    tuning: [u8; 4],
+    accidentals: Accidentals,
+    view: View,
+    theme: Theme,
+    style: Style,
+}
+
+/// A chord diagram taken apart for exports; strings are in G C E A order.
+pub(crate) struct Sketch {
+    /// The title, or the chord's name without the "Chord:" prefix
+    pub title: String,
+    pub strings: [&'static str; 4],
+    pub frets: [Option<u8>; 4],
+    pub marks: [(Part, String); 4],
+    pub window: (u8, u8),
+    pub view: View,
+}
+
+impl<'a> ChordDiagram<'a> {
+    pub fn new(chord: &'a Chord) -> Self {
+        Self {
+            chord,
+            title: None,
+            name: None,
+            window: None,
+            tuning: STANDARD_TUNING,
+            accidentals: Accidentals::default(),
+            view: View::default(),
+            theme: Theme::default(),
+            style: Style::default(),
+        }
+    }
+
+    /// Replace the default "Chord: name" title line.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Spell note and interval labels against another of the chord's names,
+    /// e.g. "Bb7" for a chord filed as "A#7".
+    pub fn spelled_as(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Draw frets `start..=end`, e.g. to line up with other diagrams. By
+    /// default the window fits this chord alone.
+    pub fn window(mut self, start: u8, end: u8) -> Self {
+        self.window = Some((start, end.max(start)));
+        self
+    }
+
+    /// Open-string pitches as MIDI notes, G C E A order.
+    pub fn tuning(mut self, tuning: [u8; 4]) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    /// Spelling for the string names.
+    pub fn accidentals(mut self, accidentals: Accidentals) -> Self {
+        self.accidentals = accidentals;
+        self
+    }
+
+    /// Orientation, handedness and string order all at once.
+    pub fn view(mut self, view: View) -> Self {
+        self.view = view;
+        self
+    }
+
+    pub fn orientation(mut self, orientation: Orientation) -> Self {
+        self.view.orientation = orientation;
+        self
+    }
+
+    pub fn left_handed(mut self, left_handed: bool) -> Self {
+        self.view.left_handed = left_handed;
+        self
+    }
+
+    pub fn string_order(mut self, order: StringOrder) -> Self {
+        self.view.string_order = order;
+        self
+    }
+
+    /// Notes, intervals or fingers in place of the dots.
+    pub fn labels(mut self, labels: Labels) -> Self {
+        self.view.labels = labels;
+        self
+    }
+
+    /// Styles for the title, labels, markers and each chord tone.
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Base style for the whole area.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Columns and rows the diagram takes.
+    pub fn size(&self) -> (u16, u16) {
+        let diagram = self.diagram();
+        (diagram.width() as u16, diagram.height() as u16)
+    }
+
+    /// The fret window drawn: as set, or fitted to the chord.
+    pub fn frets(&self) -> (u8, u8) {
+        self.window
+            .unwrap_or_else(|| fret_window([self.chord], DEFAULT_FRET_WINDOW))
+    }
+
+    /// The diagram as tagged text, for styling elsewhere or copying.
+    pub fn diagram(&self) -> Diagram {
+        let (start, end) = self.frets();
+        let strings = theory::string_names(self.tuning, self.accidentals);
+        let marks = self.marks();
+
+        let mut out = Diagram::default();
+        let title = match &self.title {
+            Some(title) => title.clone(),
+            None => format!("Chord: {}", self.chord.name),
+        };
+        out.push(Part::Title, title);
+        match self.view.orientation {
+            Orientation::Horizontal => self.horizontal(&mut out, start, end, strings, &marks),
+            Orientation::Vertical => self.vertical(&mut out, start, end, strings, &marks),
+        }
+        out
+    }
+
+    /// The pieces an export needs to draw this diagram its own way.
+    pub(crate) fn sketch(&self) -> Sketch {
+        Sketch {
+            title: match &self.title {
+                Some(title) => title.clone(),
+                None => self.name.clone().unwrap_or_else(|| self.chord.name.clone()),
+            },
+            strings: theory::string_names(self.tuning, self.accidentals),
+            frets: self.chord.frets,
+            marks: self.marks(),
+            window: self.frets(),
+            view: self.view,
+        }
+    }
+
+    /// What to draw on each string (G C E A order) at its fret or in the
+    /// open/muted column: "●", "O" or "X", or a label in their place.
+    fn marks(&self) -> [(Part, String); 4] {
+        let tones = self.chord.tones(self.tuning);
+        let name = self.name.as_deref().unwrap_or(&self.chord.name);
+        let spelling = self.chord.spelling(name, self.tuning, self.accidentals);
+        let fingers = self.chord.fingers();
+        std::array::from_fn(|i| {
+            let label = match self.view.labels {
+                Labels::Dots => None,
+                Labels::Notes => spelling[i].as_ref().map(|(note, _)| note.clone()),
+                Labels::Intervals => {
+                    spelling[i]
+                        .as_ref()
+                        .map(|(_, interval)| match interval.as_str() {
+                            "1" => "R".to_string(),
+                            other => other.to_string(),
+                        })
+                }
+                Labels::Fingers => fingers[i].map(|f| f.to_string()),
+            };
+            match self.chord.frets[i] {
+                None => (Part::Muted, "X".to_string()),
+                Some(0) => (Part::Open, label.unwrap_or_else(|| "O".to_string())),
+                Some(_) => (
+                    Part::Dot(tones[i]),
+                    label.unwrap_or_else(|| "●".to_string()),
+                ),
+            }
+        })
+    }
+
+    /// Strings as rows: a header of fret numbers, a divider showing the
+    /// inlays, then "A O║ -  ●  -". The nut is doubled when the window starts
+    /// at fret 1; otherwise the header starts with a "5fr" position label.
+    /// Left-handed, the whole row is mirrored: "-  ●  - ║O A". Cells widen
+    /// to fit labels such as "#11".
+    fn horizontal(
+        &self,
+        out: &mut Diagram,
+        start: u8,
+        end: u8,
+        strings: [&str; 4],
+        marks: &[(Part, String); 4],
+    ) {
+        let (dot_width, marker_width) = mark_widths(&self.chord.frets, marks);
+        let cell = (dot_width + 1).max(3);
+        // Columns before the first fret cell: "A O| "
+        let label_width = strings.iter().map(|s| s.len()).max().unwrap_or(1);
+        let margin = label_width + marker_width + 3;
+        let mirrored = self.view.frets_reversed();
+        let frets: Vec<u8> = if mirrored {
+            (start..=end).rev().collect()
+        } else {
+            (start..=end).collect()
+        };
+
+        // Fret numbers sit over their dots, so "10" lines up like "9"
+        let position = if start > 1 {
+            format!("{}fr", start)
+        } else {
+            String::new()
+        };
+        out.new_line();
+        if !mirrored {
+            out.push(Part::Label, format!("{:<margin$}", position));
+        }
+        for &f in &frets {
+            let number = if mirrored {
+                format!("{:>cell$}", f)
+            } else {
+                format!("{:<cell$}", f)
+            };
+            out.push(Part::Label, number);
+        }
+        if mirrored {
+            out.push(Part::Label, format!("{:>margin$}", position));
+        }
+
+        out.new_line();
+        if !mirrored {
+            out.push(Part::Fretboard, "-".repeat(margin));
+        }
+        for &f in &frets {
+            match inlay(f) {
+                Some(mark) => {
+                    let rest = "-".repeat(cell - mark.chars().count());
+                    if mirrored {
+                        out.push(Part::Fretboard, rest);
+                        out.push(Part::Label, mark);
+                    } else {
+                        out.push(Part::Label, mark);
+                        out.push(Part::Fretboard, rest);
+                    }
+                }
+                None => out.push(Part::Fretboard, "-".repeat(cell)),
+            }
+        }
+        if mirrored {
+            out.push(Part::Fretboard, "-".repeat(margin));
+        }
+
+        let nut = if start == 1 { "║" } else { "|" };
+        let padding = |text: &str, width: usize| " ".repeat(width - UnicodeWidthStr::width(text));
+        for i in self.view.strings() {
+            let fv = self.chord.frets[i];
+            let (part, mark) = &marks[i];
+            let (marker_part, marker) = if fv.is_some_and(|f| f > 0) {
+                (Part::Text, " ")
+            } else {
+                (*part, mark.as_str())
+            };
+            out.new_line();
+            if !mirrored {
+                // e.g. "G O║ "
+                out.push(Part::Label, format!("{:<w$} ", strings[i], w = label_width));
+                out.push(marker_part, marker);
+                out.push(Part::Text, padding(marker, marker_width));
+                out.push(Part::Fretboard, format!("{} ", nut));
+            }
+            for &f in &frets {
+                match (fv == Some(f), mirrored) {
+                    (true, false) => {
+                        out.push(*part, mark.clone());
+                        out.push(Part::Fretboard, padding(mark, cell));
+                    }
+                    (true, true) => {
+                        out.push(Part::Fretboard, padding(mark, cell));
+                        out.push(*part, mark.clone());
+                    }
+                    (false, false) => {
+                        out.push(Part::Fretboard, format!("-{}", " ".repeat(cell - 1)))
+                    }
+                    (false, true) => {
+                        out.push(Part::Fretboard, format!("{}-", " ".repeat(cell - 1)))
+                    }
+                }
+            }
+            if mirrored {
+                out.push(Part::Fretboard, format!(" {}", nut));
+                out.push(marker_part, marker);
+                out.push(Part::Text, padding(marker, marker_width));
+                out.push(Part::Label, format!(" {}", strings[i]));
+            }
+        }
+    }
+
+    /// Strings as columns, as in printed chord charts: names and open/muted
+    /// markers above a box of frets. The top is a double nut when the window
+    /// starts at fret 1; otherwise a "5fr" label sits beside the first fret.
+    /// Inlays are marked to the right of their frets.
+    ///
+    /// ```text
+    /// G C E A
+    /// ┌─┬─┬─┐
+    /// │ │ │ ● 5fr
+    /// ├─┼─┼─┤
+    /// │ │ ● │ •
+    /// ```
+    fn vertical(
+        &self,
+        out: &mut Diagram,
+        start: u8,
+        end: u8,
+        strings: [&str; 4],
+        marks: &[(Part, String); 4],
+    ) {
+        // Strings sit `gap` columns apart so two-letter names and labels
+        // still fit
+        let (dot_width, marker_width) = mark_widths(&self.chord.frets, marks);
+        let name_width = strings.iter().map(|s| s.len()).max().unwrap_or(1);
+        let gap = name_width.max(dot_width).max(marker_width) + 1;
+        let order = self.view.strings();
+        // Every column but the last is padded out to the next string
+        let width = |n: usize| if n + 1 < order.len() { gap } else { gap - 1 };
+        let padding = |text: &str, n: usize| " ".repeat(width(n) - UnicodeWidthStr::width(text));
+
+        out.new_line();
+        for (n, &i) in order.iter().enumerate() {
+            out.push(Part::Label, strings[i]);
+            out.push(Part::Text, padding(strings[i], n));
+        }
+
+        out.new_line();
+        for (n, &i) in order.iter().enumerate() {
+            let (part, mark) = &marks[i];
+            if self.chord.frets[i].is_some_and(|f| f > 0) {
+                out.push(Part::Text, " ".repeat(width(n)));
+            } else {
+                out.push(*part, mark.clone());
+                out.push(Part::Text, padding(mark, n));
+            }
+        }
+
+        if start == 1 {
+            fret_wire(out, gap, "═", ["╒", "╤", "╕"]);
+        } else {
+            fret_wire(out, gap, "─", ["┌", "┬", "┐"]);
+        }
+        for f in start..=end {
+            out.new_line();
+            for (n, &i) in order.iter().enumerate() {
+                let (part, mark) = &marks[i];
+                if self.chord.frets[i] == Some(f) {
+                    out.push(*part, mark.clone());
+                    out.push(Part::Text, padding(mark, n));
+                } else {
+                    out.push(Part::Fretboard, "│");
+                    out.push(Part::Text, padding("│", n));
+                }
+            }
+            if f == start && start > 1 {
+                out.push(Part::Label, format!(" {}fr", start));
+            } else if let Some(mark) = inlay(f) {
+                out.push(Part::Label, format!(" {}", mark));
+            }
+            if f < end {
+                fret_wire(out, gap, "─", ["├", "┼", "┤"]);
+            } else {
+                fret_wire(out, gap, "─", ["└", "┴", "┘"]);
+            }
+        }
+    }
+}
+
+/// Widest mark on a fretted string, and widest in the open/muted column.
+fn mark_widths(frets: &[Option<u8>; 4], marks: &[(Part, String); 4]) -> (usize, usize) {
+    let widest = |fretted: bool| {
+        frets
+            .iter()
+            .zip(marks)
+            .filter(|(fv, _)| fv.is_some_and(|f| f > 0) == fretted)
+            .map(|(_, (_, mark))| UnicodeWidthStr::width(mark.as_str()))
+            .max()
+            .unwrap_or(1)
+    };
+    (widest(true), widest(false))
+}
+
+/// A horizontal line across a vertical chord box, drawn with `wire`: left
+/// end, string crossings and right end, with strings `gap` columns apart.
+fn fret_wire(out: &mut Diagram, gap: usize, wire: &str, [left, cross, right]: [&str; 3]) {
+    let span = wire.repeat(gap - 1);
+    let mut line = left.to_string();
+    for i in 1..4 {
+        line.push_str(&span);
+        line.push_str(if i < 3 { cross } else { right });
+    }
+    out.new_line();
+    out.push(Part::Fretboard, line);
+}
+
+impl Widget for ChordDiagram<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        (&self).render(area, buf);
+    }
+}
+
+impl Widget for &ChordDiagram<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        buf.set_style(area, self.style);
+        self.diagram().render(&self.theme, area, buf);
+    }
+}
+
+/// The fretboard's position marker at `fret`, if it has one.
+pub fn inlay(fret: u8) -> Option<&'static str> {
+    match fret {
+        5 | 7 | 10 => Some("•"),
+        12 => Some("••"),
+        _ => None,
+    }
+}
+
+/// "O" for an open string, "X" for a muted one, a space otherwise.
+fn push_marker(out: &mut Diagram, fret: Option<u8>) {
+    match fret {
+        Some(0) => out.push(Part::Open, "O"),
+        None => out.push(Part::Muted, "X"),
+        _ => out.push(Part::Text, " "),
+    }
+}
+
+/// The whole neck from the nut, with a shape's fingers on it and an optional
+/// cursor, for picking frets.
+#[derive(Debug, Clone)]
+pub struct Fretboard {
+    frets: [Option<u8>; 4],
+    length: u8,
+    tuning: [u8; 4],
+    accidentals: Accidentals,
+    view: View,
+    tones: [Tone; 4],
+    cursor: Option<(usize, u8)>,
+    theme: Theme,
+    style: Style,
+}
+
+impl Fretboard {
+    /// A neck with `frets` (G C E A order) fingered on it.
+    pub fn new(frets: [Option<u8>; 4]) -> Self {
+        Self {
+            frets,
+            length: DEFAULT_NECK_FRETS,
+            tuning: STANDARD_TUNING,
+            accidentals: Accidentals::default(),
+            view: View::default(),
+            tones: [Tone::Other; 4],
+            cursor: None,
+            theme: Theme::default(),
+            style: Style::default(),
+        }
+    }
+
+    /// How many frets the neck has.
+    pub fn length(mut self, frets: u8) -> Self {
+        self.length = frets.max(1);
+        self
+    }
+
+    /// Open-string pitches as MIDI notes, G C E A order.
+    pub fn tuning(mut self, tuning: [u8; 4]) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    /// Spelling for the string names.
+    pub fn accidentals(mut self, accidentals: Accidentals) -> Self {
+        self.accidentals = accidentals;
+        self
+    }
+
+    /// Orientation, handedness and string order all at once.
+    pub fn view(mut self, view: View) -> Self {
+        self.view = view;
+        self
+    }
+
+    pub fn orientation(mut self, orientation: Orientation) -> Self {
+        self.view.orientation = orientation;
+        self
+    }
+
+    pub fn left_handed(mut self, left_handed: bool) -> Self {
+        self.view.left_handed = left_handed;
+        self
+    }
+
+    pub fn string_order(mut self, order: StringOrder) -> Self {
+        self.view.string_order = order;
+        self
+    }
+
+    /// Colour each string's dot by its role in a chord, G C E A order.
+    pub fn tones(mut self, tones: [Tone; 4]) -> Self {
+        self.tones = tones;
+        self
+    }
+
+    /// Bracket a string (index into the frets) at a fret, 0 being the nut.
+    pub fn cursor(mut self, cursor: Option<(usize, u8)>) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Base style for the whole area.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Columns and rows the neck takes.
+    pub fn size(&self) -> (u16, u16) {
+        let diagram = self.diagram();
+        (diagram.width() as u16, diagram.height() as u16)
+    }
+
+    /// Map a position relative to the widget's top-left corner to a string
+    /// (index into the frets) and fret, 0 meaning the open/muted marker.
+    pub fn hit(&self, x: u16, y: u16) -> Option<(usize, u8)> {
+        let strings = self.view.strings();
+        match self.view.orientation {
+            Orientation::Horizontal => {
+                let string = *strings.get(usize::from(y).checked_sub(1)?)?;
+                let nut = self.label_width() + NUT_WIDTH;
+                let neck = u16::from(self.length) * CELL_WIDTH;
+                let fret = if self.view.frets_reversed() {
+                    if x >= neck {
+                        return Some((string, 0));
+                    }
+                    u16::from(self.length) - x / CELL_WIDTH
+                } else {
+                    if x < nut {
+                        return Some((string, 0));
+                    }
+                    (x - nut) / CELL_WIDTH + 1
+                };
+                (fret <= u16::from(self.length)).then_some((string, fret as u8))
+            }
+            Orientation::Vertical => {
+                let column = usize::from(x.checked_sub(FRET_LABEL_WIDTH)? / STRING_WIDTH);
+                let string = *strings.get(column)?;
+                // Marker and nut rows are both fret 0
+                let fret = y.checked_sub(1)?.saturating_sub(1);
+                (fret <= u16::from(self.length)).then_some((string, fret as u8))
+            }
+        }
+    }
+
+    /// The neck as tagged text.
+    pub fn diagram(&self) -> Diagram {
+        let mut out = Diagram::default();
+        match self.view.orientation {
+            Orientation::Horizontal => self.horizontal(&mut out),
+            Orientation::Vertical => self.vertical(&mut out),
+        }
+        out
+    }
+
+    /// Strings as rows: "A  O ║─●─│───│", or "│───│─●─║ O  A" left-handed.
+    fn horizontal(&self, out: &mut Diagram) {
+        let names = theory::string_names(self.tuning, self.accidentals);
+        let label_width = self.label_width() as usize;
+        let margin = " ".repeat(label_width + NUT_WIDTH as usize);
+        let mirrored = self.view.frets_reversed();
+        let frets: Vec<u8> = if mirrored {
+            (1..=self.length).rev().collect()
+        } else {
+            (1..=self.length).collect()
+        };
+
+        if !mirrored {
+            out.push(Part::Label, margin);
+            for &f in &frets {
+                out.push(Part::Label, format!("{:^3} ", f));
+            }
+        } else {
+            for &f in &frets {
+                out.push(Part::Label, format!(" {:^3}", f));
+            }
+        }
+
+        for i in self.view.strings() {
+            out.new_line();
+            if mirrored {
+                for &f in &frets {
+                    out.push(Part::Fretboard, "│");
+                    self.cell(out, i, f);
+                }
+                out.push(Part::Fretboard, "║");
+                self.cell(out, i, 0);
+                out.push(Part::Label, format!(" {}", names[i]));
+            } else {
+                out.push(Part::Label, format!("{:<w$} ", names[i], w = label_width));
+                self.cell(out, i, 0);
+                out.push(Part::Fretboard, "║");
+                for &f in &frets {
+                    self.cell(out, i, f);
+                    out.push(Part::Fretboard, "│");
+                }
+            }
+        }
+    }
+
+    /// Strings as columns: names, markers and the nut above one row per fret.
+    fn vertical(&self, out: &mut Diagram) {
+        let names = theory::string_names(self.tuning, self.accidentals);
+        let order = self.view.strings();
+        let prefix = " ".repeat(FRET_LABEL_WIDTH as usize);
+        out.push(Part::Label, prefix.clone());
+        for i in order {
+            out.push(Part::Label, format!(" {:<3}", names[i]));
+        }
+
+        out.new_line();
+        out.push(Part::Text, prefix.clone());
+        for i in order {
+            self.cell(out, i, 0);
+            out.push(Part::Text, " ");
+        }
+
+        out.new_line();
+        out.push(Part::Label, prefix);
+        out.push(
+            Part::Fretboard,
+            "═".repeat((STRING_WIDTH as usize) * order.len()),
+        );
+
+        for f in 1..=self.length {
+            out.new_line();
+            out.push(Part::Label, format!("{:>2} ", f));
+            for i in order {
+                self.cell(out, i, f);
+                out.push(Part::Text, " ");
+            }
+        }
+    }
+
+    /// Three columns for one string at one fret: the marker at the nut, a
+    /// dot or bare string elsewhere, bracketed under the cursor.
+    fn cell(&self, out: &mut Diagram, string: usize, fret: u8) {
+        let fv = self.frets[string];
+        let under_cursor = self.cursor == Some((string, fret));
+        let horizontal = self.view.orientation == Orientation::Horizontal;
+        let bare = if horizontal { "─" } else { "│" };
+        let (part, open, close) = if under_cursor {
+            (Part::Cursor, "[", "]")
+        } else if fret > 0 && horizontal {
+            (Part::Fretboard, bare, bare)
+        } else {
+            // Vertical strings only run through the middle of the cell
+            (Part::Text, " ", " ")
+        };
+        out.push(part, open);
+        if fret == 0 {
+            push_marker(out, fv);
+        } else if fv == Some(fret) {
+            out.push(Part::Dot(self.tones[string]), "●");
+        } else {
+            out.push(Part::Fretboard, bare);
+        }
+        out.push(part, close);
+    }
+
+    /// Widest string name, e.g. 2 for a tuning with "Bb".
+    fn label_width(&self) -> u16 {
+        theory::string_names(self.tuning, self.accidentals)
+            .iter()
+            .map(|n| n.len() as u16)
+            .max()
+            .unwrap_or(1)
+    }
+}
+
+impl Widget for Fretboard {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        (&self).render(area, buf);
+    }
+}
+
+impl Widget for &Fretboard {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        buf.set_style(area, self.style);
+        self.diagram().render(&self.theme, area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(frets: [Option<u8>; 4]) -> Chord {
+        Chord::new("C".into(), frets).unwrap()
+    }
+
+    /// Render through the widget and read the buffer back, one string per row.
+    fn rows(widget: impl Widget, (width, height): (u16, u16)) -> Vec<String> {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        widget.render(area, &mut buf);
+        (0..height)
+            .map(|y| (0..width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect()
+    }
+
+    fn diagram_rows(diagram: &ChordDiagram) -> Vec<String> {
+        rows(diagram, diagram.size())
+    }
+
+    /// Column of the first `needle` in `row`, counting buffer cells.
+    fn column(row: &str, needle: &str) -> Option<usize> {
+        let at = row.find(needle)?;
+        Some(row[..at].chars().count())
+    }
+
+    #[test]
+    fn horizontal_nut_is_doubled_only_at_fret_one() {
+        let open = chord([Some(0), Some(0), Some(0), Some(3)]);
+        let low = diagram_rows(&ChordDiagram::new(&open).window(1, 5));
+        assert!(low[3..].iter().all(|row| row.contains('║')), "{low:#?}");
+        assert!(!low[1].contains("fr"));
+
+        let high = chord([Some(9), Some(9), Some(8), Some(10)]);
+        let up = diagram_rows(&ChordDiagram::new(&high).window(8, 12));
+        assert!(
+            up[3..]
+                .iter()
+                .all(|row| row.contains('|') && !row.contains('║'))
+        );
+        assert!(up[1].starts_with("8fr"), "{up:#?}");
+    }
+
+    #[test]
+    fn horizontal_inlays_sit_under_their_fret_numbers() {
+        let open = chord([Some(0), Some(0), Some(0), Some(3)]);
+        let rows = diagram_rows(&ChordDiagram::new(&open).window(1, 12));
+        let (header, divider) = (&rows[1], &rows[2]);
+        let inlays: Vec<usize> = divider
+            .chars()
+            .enumerate()
+            .filter(|&(_, c)| c == '•')
+            .map(|(x, _)| x)
+            .collect();
+        let at = |fret: &str| column(header, &format!(" {fret}")).unwrap() + 1;
+        // Fret 12's double inlay takes two cells
+        assert_eq!(inlays, [at("5"), at("7"), at("10"), at("12"), at("12") + 1]);
+    }
+
+    #[test]
+    fn horizontal_two_digit_frets_line_up_with_their_dots() {
+        let high = chord([Some(10), Some(11), Some(12), None]);
+        let rows = diagram_rows(&ChordDiagram::new(&high).window(10, 12));
+        let header = rows[1].replacen("10fr", "    ", 1);
+        // A on top: the muted A, then E at 12, C at 11 and G at 10
+        for (row, fret) in rows[4..].iter().zip(["12", "11", "10"]) {
+            assert_eq!(column(row, "●"), column(&header, fret), "{rows:#?}");
+        }
+    }
+
+    fn views() -> Vec<View> {
+        let mut views = Vec::new();
+        for orientation in [Orientation::Horizontal, Orientation::Vertical] {
+            for left_handed in [false, true] {
+                for string_order in [StringOrder::ATop, StringOrder::GTop] {
+                    views.push(View {
+                        orientation,
+                        left_handed,
+                        string_order,
+                        labels: Labels::Dots,
+                    });
+                }
+            }
+        }
+        views
+    }
+
+    #[test]
+    fn strings_turn_and_mirror_with_the_view() {
+        let (h, v) = (Orientation::Horizontal, Orientation::Vertical);
+        let (a, g) = (StringOrder::ATop, StringOrder::GTop);
+        let cases = [
+            // Top to bottom: mirroring a row doesn't reorder the rows
+            (h, false, a, [3, 2, 1, 0], false),
+            (h, false, g, [0, 1, 2, 3], false),
+            (h, true, a, [3, 2, 1, 0], true),
+            (h, true, g, [0, 1, 2, 3], true),
+            // Left to right: the top string ends up on the right
+            (v, false, a, [0, 1, 2, 3], false),
+            (v, false, g, [3, 2, 1, 0], false),
+            (v, true, a, [3, 2, 1, 0], false),
+            (v, true, g, [0, 1, 2, 3], false),
+        ];
+        for (orientation, left_handed, string_order, strings, reversed) in cases {
+            let view = View {
+                orientation,
+                left_handed,
+                string_order,
+                labels: Labels::Dots,
+            };
+            assert_eq!(view.strings(), strings, "{view:?}");
+            assert_eq!(view.frets_reversed(), reversed, "{view:?}");
+        }
+    }
+
+    #[test]
+    fn left_handed_diagrams_are_mirrored() {
+        let c = chord([Some(0), Some(0), Some(0), Some(3)]);
+        let right = diagram_rows(&ChordDiagram::new(&c).window(1, 4));
+        let left = diagram_rows(&ChordDiagram::new(&c).window(1, 4).left_handed(true));
+        // Horizontal: the nut and string names move to the right
+        assert!(right[3].starts_with("A  ║ "), "{right:#?}");
+        assert!(left[3].trim_end().ends_with(" ║  A"), "{left:#?}");
+        assert!(column(&left[3], "●") < column(&left[3], "║"));
+        assert!(left[4].trim_end().ends_with(" ║O E"));
+
+        // Vertical: the strings swap sides
+        let boxes = |left_handed| {
+            let diagram = ChordDiagram::new(&c)
+                .window(1, 4)
+                .orientation(Orientation::Vertical)
+                .left_handed(left_handed);
+            diagram_rows(&diagram)[1].trim_end().to_string()
+        };
+        assert_eq!(boxes(false), "G C E A");
+        assert_eq!(boxes(true), "A E C G");
+    }
+
+    #[test]
+    fn fretboard_hits_land_on_what_is_drawn() {
+        let frets = [Some(0), None, Some(3), Some(5)];
+        for view in views() {
+            let fretboard = Fretboard::new(frets).view(view);
+            let size = fretboard.size();
+            let mut seen = 0;
+            for (y, row) in rows(&fretboard, size).iter().enumerate() {
+                for (x, symbol) in row.chars().enumerate() {
+                    let shown = match symbol {
+                        'O' => Some(0),
+                        'X' => None,
+                        '●' => Some(1),
+                        _ => continue,
+                    };
+                    let (string, fret) = fretboard.hit(x as u16, y as u16).unwrap();
+                    match shown {
+                        Some(1) => assert_eq!(frets[string], Some(fret), "{view:?}"),
+                        _ => assert_eq!((frets[string], fret), (shown, 0), "{view:?}"),
+                    }
+                    seen += 1;
+                }
+            }
+            assert_eq!(seen, 4, "{view:?}");
+        }
+    }
+
+    #[test]
+    fn mirrored_fretboard_runs_right_to_left_from_the_nut() {
+        let fretboard = Fretboard::new([Some(0); 4]).left_handed(true);
+        let (width, _) = fretboard.size();
+        // Row 1 is the top string, A; the highest fret is on the left
+        assert_eq!(fretboard.hit(0, 1), Some((3, 12)));
+        assert_eq!(fretboard.hit(CELL_WIDTH, 1), Some((3, 11)));
+        assert_eq!(fretboard.hit(12 * CELL_WIDTH - 1, 4), Some((0, 1)));
+        assert_eq!(fretboard.hit(width - 1, 4), Some((0, 0)));
+        assert_eq!(fretboard.hit(0, 0), None);
+        assert_eq!(fretboard.hit(0, 5), None);
+    }
+
+    fn vertical(chord: &Chord) -> ChordDiagram<'_> {
+        ChordDiagram::new(chord).orientation(Orientation::Vertical)
+    }
+
+    #[test]
+    fn vertical_boxes_run_g_c_e_a_with_markers_above_the_nut() {
+        let c = chord([Some(0), None, Some(0), Some(3)]);
+        let rows = diagram_rows(&vertical(&c).window(1, 4));
+        assert_eq!(rows[1].trim_end(), "G C E A");
+        assert_eq!(rows[2].trim_end(), "O X O");
+        assert_eq!(rows[3].trim_end(), "╒═╤═╤═╕");
+        // The A string's dot on the third fret
+        assert_eq!(rows[8].trim_end(), "│ │ │ ●");
+    }
+
+    #[test]
+    fn vertical_position_label_only_above_fret_one() {
+        let open = chord([Some(0), Some(0), Some(0), Some(3)]);
+        let low = diagram_rows(&vertical(&open).window(1, 4));
+        assert!(low.iter().all(|row| !row.contains("fr")), "{low:#?}");
+
+        let high = chord([Some(7), Some(7), Some(8), Some(5)]);
+        let up = diagram_rows(&vertical(&high).window(5, 8));
+        assert_eq!(up[3].trim_end(), "┌─┬─┬─┐");
+        assert_eq!(up[4].trim_end(), "│ │ │ ● 5fr");
+        assert_eq!(up.iter().filter(|row| row.contains("fr")).count(), 1);
+        // The inlay at 7 still shows
+        assert_eq!(up[8].trim_end(), "● ● │ │ •");
+    }
+
+    fn named(name: &str, frets: [Option<u8>; 4]) -> Chord {
+        Chord::new(name.into(), frets).unwrap()
+    }
+
+    /// Width of every row after the title, which is allowed to differ.
+    fn widths(rows: &[String]) -> Vec<usize> {
+        rows[1..]
+            .iter()
+            .map(|row| UnicodeWidthStr::width(row.trim_end()))
+            .collect()
+    }
+
+    #[test]
+    fn interval_labels_widen_the_cells() {
+        let c7 = named("C7", [Some(0), Some(0), Some(0), Some(1)]);
+        let diagram = ChordDiagram::new(&c7)
+            .window(1, 3)
+            .labels(Labels::Intervals);
+        let rows = diagram_rows(&diagram);
+        // A on top: b7 at the first fret, then the open E, C and G
+        assert_eq!(rows[3].trim_end(), "A  ║ b7 -  -");
+        assert_eq!(rows[4].trim_end(), "E 3║ -  -  -");
+        assert_eq!(rows[5].trim_end(), "C R║ -  -  -");
+        assert_eq!(rows[6].trim_end(), "G 5║ -  -  -");
+        let widths = widths(&rows);
+        assert!(widths[2..].iter().all(|&w| w == widths[2]), "{rows:#?}");
+
+        let boxes = diagram_rows(&vertical(&c7).window(1, 3).labels(Labels::Intervals));
+        assert_eq!(boxes[2].trim_end(), "5  R  3");
+        assert_eq!(boxes[4].trim_end(), "│  │  │  b7");
+        assert!(on_strings(&boxes[4], &boxes[3]), "{boxes:#?}");
+    }
+
+    #[test]
+    fn note_labels_fit_two_characters() {
+        let b_flat = named("Bb7", [Some(1), Some(2), Some(1), Some(1)]);
+        let diagram = ChordDiagram::new(&b_flat)
+            .window(1, 3)
+            .labels(Labels::Notes);
+        let rows = diagram_rows(&diagram);
+        assert_eq!(rows[3].trim_end(), "A  ║ Bb -  -");
+        assert_eq!(rows[5].trim_end(), "C  ║ -  D  -");
+        assert_eq!(rows[6].trim_end(), "G  ║ Ab -  -");
+        let widths = widths(&rows);
+        assert!(widths[2..].iter().all(|&w| w == widths[2]), "{rows:#?}");
+
+        let boxes = diagram_rows(&vertical(&b_flat).window(1, 3).labels(Labels::Notes));
+        assert_eq!(boxes[4].trim_end(), "Ab │  F  Bb");
+        assert!(on_strings(&boxes[4], &boxes[3]), "{boxes:#?}");
+    }
+
+    /// Whether each piece of `row` starts on a string, where one crosses
+    /// `wire`.
+    fn on_strings(row: &str, wire: &str) -> bool {
+        let wire: Vec<char> = wire.chars().collect();
+        let mut before = ' ';
+        row.chars().enumerate().all(|(x, c)| {
+            let starts = before == ' ' && c != ' ';
+            before = c;
+            !starts || wire.get(x).is_some_and(|w| "╒╤╕┌┬┐├┼┤└┴┘".contains(*w))
+        })
+    }
+}